@@ -0,0 +1,4 @@
+"""
+code shared between the unity-slurm-* commands in ../../bin
+standard library only, like the commands themselves
+"""
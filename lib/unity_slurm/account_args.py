@@ -0,0 +1,95 @@
+"""
+the arguments that choose which accounts the account usage tools show
+
+    (default)           the user's POSIX groups that start with "pi_"
+    --all-my-accounts   every account that the user has a slurm association with
+    --account A,B       these accounts, whether or not the user is in them
+    --user U            the user for the other two, default: you
+
+slurm decides what you are allowed to see, coordinators and helpdesk staff can see the jobs
+of any account that they have rights to.
+"""
+import os
+import sys
+import subprocess as subp  # nosec
+from typing import List, Optional
+
+from unity_slurm import slurm, limits
+
+ACCOUNT_ARG = "--account"
+USER_ARG = "--user"
+ALL_MY_ACCOUNTS_ARG = "--all-my-accounts"
+GROUPS_CMD = "/usr/bin/groups"
+PI_GROUP_PREFIX = "pi_"
+
+HELP_LINES = [
+    f"{ACCOUNT_ARG} A,B: show these accounts rather than your PI groups, for example as a coordinator",
+    f"{ALL_MY_ACCOUNTS_ARG}: show every account that you have a slurm association with",
+    f"{USER_ARG} USER: show USER's PI groups or accounts rather than your own",
+]
+
+
+def _pop_value_arg(argv: List[str], flag: str) -> Optional[str]:
+    for i, arg in enumerate(argv):
+        if arg == flag:
+            if i + 1 >= len(argv):
+                sys.exit(f"{flag} requires an argument")
+            value = argv[i + 1]
+            del argv[i : i + 2]
+            return value
+        if arg.startswith(flag + "="):
+            del argv[i]
+            return arg.split("=", 1)[1]
+    return None
+
+
+def posix_pi_groups() -> List[str]:
+    if slurm.snapshot_dir() is not None:
+        my_groups = slurm.my_posix_groups()
+        if my_groups is None:
+            # the snapshot doesn't know my POSIX groups, use the accounts of my associations
+            my_groups = association_accounts(slurm.current_user())
+        return sorted(x for x in my_groups if x.startswith(PI_GROUP_PREFIX))
+    argv = [GROUPS_CMD]
+    if slurm.user_override() is not None:
+        argv.append(slurm.user_override())
+    # `groups USER` prints "USER : GROUP GROUP ...", `groups` prints "GROUP GROUP ..."
+    groups = subp.check_output(argv, text=True, timeout=1).split(":")[-1].split()  # nosec
+    return [x for x in groups if x.startswith(PI_GROUP_PREFIX)]
+
+
+def association_accounts(user: str, timeout: Optional[float] = None) -> List[str]:
+    associations = limits.all_associations(timeout=timeout)
+    return sorted({x["account"] for x in associations if x["user"] == user})
+
+
+def pop_account_args(argv: List[str], timeout: Optional[float] = None) -> List[str]:
+    """
+    remove the arguments above from argv (in place) and return the accounts to show
+    timeout: seconds to wait for sacctmgr, raises subprocess.TimeoutExpired after that
+    """
+    account_arg = _pop_value_arg(argv, ACCOUNT_ARG)
+    user = _pop_value_arg(argv, USER_ARG)
+    all_my_accounts = ALL_MY_ACCOUNTS_ARG in argv
+    if all_my_accounts:
+        argv.remove(ALL_MY_ACCOUNTS_ARG)
+    if account_arg is not None and (all_my_accounts or user is not None):
+        sys.exit(f"{ACCOUNT_ARG} can't be used with {ALL_MY_ACCOUNTS_ARG} or {USER_ARG}")
+    if user is not None:
+        # the same as `unity-slurm --user`, so that current_user() agrees
+        os.environ[slurm.USER_ENV_VAR] = user
+    if account_arg is not None:
+        requested = [x.strip() for x in account_arg.split(",") if x.strip() != ""]
+        if not requested:
+            sys.exit(f"{ACCOUNT_ARG} requires at least one account")
+        existing = {x["account"] for x in limits.all_associations(timeout=timeout)}
+        for account in requested:
+            if account not in existing:
+                sys.exit(f'account "{account}" does not exist')
+        return requested
+    if all_my_accounts:
+        accounts = association_accounts(slurm.current_user(), timeout=timeout)
+        if not accounts:
+            sys.exit(f'user "{slurm.current_user()}" has no slurm accounts')
+        return accounts
+    return posix_pi_groups()
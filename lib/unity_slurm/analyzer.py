@@ -0,0 +1,242 @@
+"""
+per node and per partition usage, and which partitions the current user can access
+
+used by unity-slurm-node-usage, unity-slurm-partition-usage and unity-compute
+"""
+import os
+import sys
+import subprocess as subp
+from typing import List, Optional
+
+from unity_slurm import config, slurm, schema
+
+SINFO_CACHE_FILE_PATH = os.getenv(
+    "SINFO_CACHE_FILE_PATH", config.get("cache_files")["sinfo"]
+)
+# unity-slurm-gpu-list used to read SINFO_CACHE_FILE
+SINFO_N_CACHE_FILE_PATH = os.getenv(
+    "SINFO_N_CACHE_FILE_PATH",
+    os.getenv("SINFO_CACHE_FILE", config.get("cache_files")["sinfo-N"]),
+)
+DOWN_STATES = set(config.get("down_states"))
+
+
+def any_elem_is_in_list(any_of_these: list, in_this_list: list) -> bool:
+    return any((x in in_this_list) for x in any_of_these)
+
+
+def split_commas_strip_remove_empty_strings(list_str: str) -> list:
+    return [x.strip() for x in list_str.split(",") if x.strip() != ""]
+
+
+class SlurmNodeUsageAnalyzer:
+    def __init__(self, associations_timeout_s: Optional[float] = None):
+        """
+        associations_timeout_s: give up on sacctmgr, which waits on slurmdbd, after this long.
+        then `access_known` is False, and check_partition_access can't be trusted
+        """
+        self.associations_timeout_s = associations_timeout_s
+        self.access_known = True
+        self.my_posix_groups = slurm.my_posix_groups() or []
+        self.sinfo_n, self.sinfo, self.squeue, self.my_associations = (
+            None,
+            None,
+            None,
+            None,
+        )
+        self.my_slurm_accounts, self.my_qos = list(), list()
+        self.nodes, self.partitions, self.node_partitions = dict(), dict(), dict()
+        self.down_nodes = set()
+        # hostname: list of the running jobs on that node and what they have allocated there
+        self.node_jobs = dict()
+        self.num_untrackable_gpus = 0
+        print("collecting info from slurm...", file=sys.stderr)
+        self.get_slurm_input()
+        self.parse_slurm_input()
+
+    def get_slurm_input(self):
+        self.sinfo_n = schema.normalize_sinfo(
+            slurm.slurm_json(
+                "sinfo-N",
+                [slurm.command("sinfo"), "--all", "-N", "--json"],
+                cache_file_path=SINFO_N_CACHE_FILE_PATH,
+            )
+        )
+        self.sinfo = schema.normalize_sinfo(
+            slurm.slurm_json(
+                "sinfo",
+                [slurm.command("sinfo"), "--all", "--json"],
+                cache_file_path=SINFO_CACHE_FILE_PATH,
+            )
+        )
+        self.squeue = schema.normalize_squeue(
+            slurm.slurm_json("squeue", [slurm.command("squeue"), "--all", "--json"])
+        )
+        try:
+            self.my_associations = schema.normalize_associations(
+                slurm.slurm_json(
+                    "sacctmgr-associations",
+                    [
+                        slurm.command("sacctmgr"),
+                        "show",
+                        "association",
+                        "--json",
+                        f"user={slurm.current_user()}",
+                    ],
+                    user=slurm.current_user(),
+                    timeout=self.associations_timeout_s,
+                )
+            )
+        except subp.TimeoutExpired:
+            print(
+                f"sacctmgr did not answer within {self.associations_timeout_s:g} seconds,"
+                " so your access to partitions is unknown",
+                file=sys.stderr,
+            )
+            self.my_associations = []
+            self.access_known = False
+
+    def parse_slurm_input(self) -> None:
+        self.my_slurm_accounts = [x["account"] for x in self.my_associations]
+        self.my_qos = sorted({qos for x in self.my_associations for qos in x["qos"]})
+        for sinfo_element in self.sinfo:
+            partition = sinfo_element["partition"]
+            partition_name = partition["name"]
+            for hostname in sinfo_element["nodes"]:
+                try:
+                    self.node_partitions[hostname].add(partition_name)
+                except KeyError:
+                    self.node_partitions[hostname] = set()
+                    self.node_partitions[hostname].add(partition_name)
+            # the same partition can occur in multiple sinfo elements
+            if partition_name in self.partitions:
+                if self.partitions[partition_name] == partition:
+                    continue
+                else:
+                    raise RuntimeError(
+                        "the same partition occurs multiple times with different information!"
+                    )
+            else:
+                self.partitions[partition_name] = partition
+
+        for sinfo_node in self.sinfo_n:
+            assert len(sinfo_node["nodes"]) == 1
+            name = sinfo_node["nodes"][0]
+            # the node can occur multiple sinfo elements, once per partition
+            if name in self.nodes or name in self.down_nodes:
+                continue
+            if any([state in DOWN_STATES for state in sinfo_node["state"]]):
+                self.down_nodes.add(name)
+                continue
+            gpu_type, total_gpus = schema.parse_gres_gpus(sinfo_node["gres"]) or ("", 0)
+            self.nodes[name] = {
+                "total_cpus": sinfo_node["cpus"],
+                "alloc_cpus": 0,
+                "total_gpus": total_gpus,
+                "gpu_type": gpu_type,
+                "alloc_gpus": 0,
+                "total_mem_MB": sinfo_node["memory_MB"],
+                "alloc_mem_MB": 0,
+            }
+
+        for job in self.squeue["jobs"]:
+            if not schema.job_has_state(job, "RUNNING"):
+                continue
+            job_gpus, _ = schema.tres_gpus(job["tres_alloc_str"])
+            # if this job is running on >1 node, we don't know on which nodes the GPUs are allocated
+            node_gpus = job_gpus if job["node_count"] == 1 else 0
+            for allocated_node in job["allocated_nodes"]:
+                hostname = allocated_node["nodename"]
+                if hostname in self.down_nodes:
+                    continue
+                self.nodes[hostname]["alloc_cpus"] += allocated_node["cpus"]
+                self.nodes[hostname]["alloc_mem_MB"] += allocated_node["memory_allocated"]
+                self.nodes[hostname]["alloc_gpus"] += node_gpus
+                self.node_jobs.setdefault(hostname, []).append(
+                    {
+                        "job_id": job["job_id"],
+                        "end_time": job["end_time"],
+                        "cpus": allocated_node["cpus"],
+                        "mem_MB": allocated_node["memory_allocated"],
+                        "gpus": node_gpus,
+                    }
+                )
+            if job["node_count"] > 1:
+                self.num_untrackable_gpus += job_gpus
+
+    def check_partition_access(self, partition_name: str, account=None, qos=None) -> bool:
+        """
+        slurm says that it already hides partitions that the user doesn't have access to
+        but it seems that slurm does not pay attention to allowed accounts and denied accounts
+        so I do it myself
+        account, qos: check for a job submitted with this account / QOS rather than any of mine
+        """
+        my_slurm_accounts = self.my_slurm_accounts if account is None else [account]
+        my_qos = self.my_qos if qos is None else [qos]
+        partition = self.partitions[partition_name]
+        allowed_accts = split_commas_strip_remove_empty_strings(
+            partition["accounts"]["allowed"]
+        )
+        denied_accts = split_commas_strip_remove_empty_strings(
+            partition["accounts"]["deny"]
+        )
+        allowed_qos = split_commas_strip_remove_empty_strings(
+            partition["qos"]["allowed"]
+        )
+        denied_qos = split_commas_strip_remove_empty_strings(partition["qos"]["deny"])
+        allowed_groups = split_commas_strip_remove_empty_strings(
+            partition["groups"]["allowed"]
+        )
+        if len(allowed_accts) > 0 and not any_elem_is_in_list(
+            my_slurm_accounts, allowed_accts
+        ):
+            return False
+        if len(denied_accts) > 0 and any_elem_is_in_list(
+            my_slurm_accounts, denied_accts
+        ):
+            return False
+        if len(allowed_qos) > 0 and not any_elem_is_in_list(my_qos, allowed_qos):
+            return False
+        if len(denied_qos) > 0 and any_elem_is_in_list(my_qos, denied_qos):
+            return False
+        if len(allowed_groups) > 0 and not any_elem_is_in_list(
+            self.my_posix_groups, allowed_groups
+        ):
+            return False
+        return True
+
+    def node_partitions_that_I_can_access(self, hostname: str) -> List[str]:
+        return sorted(
+            [
+                x
+                for x in self.node_partitions[hostname]
+                if self.check_partition_access(x)
+            ]
+        )
+
+    def partition_usage(self) -> dict:
+        output = {}
+        for node, node_usage in self.nodes.items():
+            for partition in self.node_partitions[node]:
+                if partition not in output:
+                    output[partition] = {}
+                output[partition]["nodes"] = output[partition].get("nodes", 0) + 1
+                output[partition]["total_cpus"] = (
+                    output[partition].get("total_cpus", 0) + node_usage["total_cpus"]
+                )
+                output[partition]["idle_cpus"] = output[partition].get(
+                    "idle_cpus", 0
+                ) + (node_usage["total_cpus"] - node_usage["alloc_cpus"])
+                output[partition]["total_mem_MB"] = (
+                    output[partition].get("total_mem_MB", 0) + node_usage["total_mem_MB"]
+                )
+                output[partition]["idle_mem_MB"] = output[partition].get(
+                    "idle_mem_MB", 0
+                ) + (node_usage["total_mem_MB"] - node_usage["alloc_mem_MB"])
+                output[partition]["total_gpus"] = (
+                    output[partition].get("total_gpus", 0) + node_usage["total_gpus"]
+                )
+                output[partition]["idle_gpus"] = output[partition].get(
+                    "idle_gpus", 0
+                ) + (node_usage["total_gpus"] - node_usage["alloc_gpus"])
+        return output
@@ -0,0 +1,59 @@
+"""
+site configuration, see docs/configuration.md
+
+each file is a JSON object, read in this order. later files override earlier ones:
+* SYSTEM_CONFIG_PATH, which ships with these commands
+* USER_CONFIG_PATH
+* the file named by the UNITY_SLURM_CONFIG environment variable
+a key that holds an object is merged with the earlier object, anything else is replaced.
+"""
+import os
+import sys
+import json
+import functools
+
+SYSTEM_CONFIG_PATH = os.path.normpath(
+    os.path.join(os.path.dirname(os.path.realpath(__file__)), "..", "..", "etc", "unity-slurm.json")
+)
+USER_CONFIG_PATH = os.path.join(os.path.expanduser("~"), ".config", "unity-slurm", "config.json")
+CONFIG_ENV_VAR = "UNITY_SLURM_CONFIG"
+
+
+def config_paths() -> list:
+    paths = [SYSTEM_CONFIG_PATH, USER_CONFIG_PATH]
+    if os.environ.get(CONFIG_ENV_VAR):
+        paths.append(os.environ[CONFIG_ENV_VAR])
+    return paths
+
+
+def _read_config_file(path: str) -> dict:
+    try:
+        with open(path, "r", encoding="utf8") as file:
+            output = json.load(file)
+    except FileNotFoundError:
+        if path == os.environ.get(CONFIG_ENV_VAR):
+            sys.exit(f'{CONFIG_ENV_VAR}="{path}" but that file does not exist!')
+        return {}
+    except ValueError as e:
+        sys.exit(f'invalid config file "{path}": {e}')
+    if not isinstance(output, dict):
+        sys.exit(f'invalid config file "{path}": expected a JSON object')
+    return output
+
+
+@functools.lru_cache(maxsize=None)
+def load_config() -> dict:
+    output = {}
+    for path in config_paths():
+        for key, value in _read_config_file(path).items():
+            if path != SYSTEM_CONFIG_PATH and key not in output:
+                print(f'warning: unknown key "{key}" in config file "{path}"', file=sys.stderr)
+            if isinstance(value, dict) and isinstance(output.get(key), dict):
+                output[key] = {**output[key], **value}
+            else:
+                output[key] = value
+    return output
+
+
+def get(key: str):
+    return load_config()[key]
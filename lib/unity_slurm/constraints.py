@@ -0,0 +1,178 @@
+"""
+parse and evaluate `--constraint` expressions, see CONSTRAINTS in `man sbatch`
+
+    intel               a node feature
+    intel&avx512        AND
+    a100|v100           OR
+    [rack1|rack2]       matching OR, all of the job's nodes have the same one of them
+    a100*2              at least 2 of the job's nodes have the feature
+    (a|b)&c             parentheses group, and can have a count too: (a&b)*2
+
+like slurm, & and | have the same precedence and are evaluated from left to right, so
+"a|b&c" means "(a|b)&c". a parsed expression is a nested tuple:
+
+    ("feature", name, count)    count is None without "*N"
+    ("&", left, right)
+    ("|", left, right)
+    ("[]", inner)
+    ("()", inner, count)
+"""
+import re
+from typing import Dict, Iterable, List, Optional, Tuple
+
+# node features are usually lowercase words, but slurm allows more than that
+TOKEN_REGEX = re.compile(
+    r"\s*(?:(?P<feature>[A-Za-z0-9_.:=+\-/]+)|(?P<count>\*[0-9]+)|(?P<op>[&|()\[\]]))"
+)
+
+
+class ConstraintError(ValueError):
+    pass
+
+
+def _tokenize(expression: str) -> List[str]:
+    tokens = []
+    position = 0
+    expression = expression.rstrip()
+    while position < len(expression):
+        match = TOKEN_REGEX.match(expression, position)
+        if not match:
+            raise ConstraintError(
+                f'invalid constraint "{expression}", unexpected "{expression[position:].strip()}"'
+            )
+        tokens.append(match.group(match.lastgroup))
+        position = match.end()
+    return tokens
+
+
+def parse(expression: str) -> tuple:
+    """
+    raises ConstraintError if the expression is not valid
+    """
+    tokens = _tokenize(expression)
+    if len(tokens) == 0:
+        raise ConstraintError("the constraint is empty")
+
+    def error(message: str) -> ConstraintError:
+        return ConstraintError(f'invalid constraint "{expression}", {message}')
+
+    def peek() -> Optional[str]:
+        return tokens[0] if tokens else None
+
+    def take_count() -> Optional[int]:
+        if peek() is not None and peek().startswith("*"):
+            return int(tokens.pop(0)[1:])
+        return None
+
+    def parse_expression(in_brackets: bool) -> tuple:
+        left = parse_term(in_brackets)
+        while peek() in ["&", "|"]:
+            op = tokens.pop(0)
+            left = (op, left, parse_term(in_brackets))
+        return left
+
+    def parse_term(in_brackets: bool) -> tuple:
+        token = tokens.pop(0) if tokens else None
+        if token == "(":
+            inner = parse_expression(in_brackets)
+            if tokens[:1] != [")"]:
+                raise error('a "(" is not closed')
+            tokens.pop(0)
+            return ("()", inner, take_count())
+        if token == "[":
+            if in_brackets:
+                raise error("brackets can't be nested")
+            inner = parse_expression(True)
+            if tokens[:1] != ["]"]:
+                raise error('a "[" is not closed')
+            tokens.pop(0)
+            return ("[]", inner)
+        if token is None or token in "&|()[]" or token.startswith("*"):
+            raise error(f'expected a feature name, not "{token or "the end"}"')
+        return ("feature", token, take_count())
+
+    tree = parse_expression(False)
+    if tokens:
+        raise error(f'unexpected "{tokens[0]}"')
+    return tree
+
+
+def features(tree: tuple) -> List[str]:
+    """
+    every feature name in the expression, in order, without duplicates
+    """
+    if tree[0] == "feature":
+        return [tree[1]]
+    output: List[str] = []
+    for child in tree[1:]:
+        if isinstance(child, tuple):
+            output += [x for x in features(child) if x not in output]
+    return output
+
+
+def matches(tree: tuple, node_features: Iterable[str]) -> bool:
+    """
+    could this node be one of the job's nodes. counts and matching OR are about the job's
+    nodes as a group, see counts()
+    """
+    node_features = set(node_features)
+    if tree[0] == "feature":
+        return tree[1] in node_features
+    if tree[0] == "&":
+        return matches(tree[1], node_features) and matches(tree[2], node_features)
+    if tree[0] == "|":
+        return matches(tree[1], node_features) or matches(tree[2], node_features)
+    return matches(tree[1], node_features)
+
+
+def is_conjunction(tree: tuple) -> bool:
+    """
+    true if a node must have every feature in the expression, there is no "|"
+    """
+    if tree[0] == "feature":
+        return True
+    if tree[0] == "|":
+        return False
+    return all(is_conjunction(x) for x in tree[1:] if isinstance(x, tuple))
+
+
+def counts(tree: tuple) -> List[Tuple[tuple, int]]:
+    """
+    [(sub expression, count)] for each "*N", at least N of the job's nodes match each one.
+    the counts on either side of a "|" are left out, since the other side could be used
+    """
+    if tree[0] == "feature":
+        return [(tree, tree[2])] if tree[2] is not None else []
+    if tree[0] == "|":
+        return []
+    output = []
+    if tree[0] == "()" and tree[2] is not None:
+        output.append((tree, tree[2]))
+    for child in tree[1:]:
+        if isinstance(child, tuple):
+            output += counts(child)
+    return output
+
+
+def to_string(tree: tuple) -> str:
+    """
+    the inverse of parse, without the spaces
+    """
+
+    def count_str(count: Optional[int]) -> str:
+        return "" if count is None else f"*{count}"
+
+    if tree[0] == "feature":
+        return tree[1] + count_str(tree[2])
+    if tree[0] == "[]":
+        return f"[{to_string(tree[1])}]"
+    if tree[0] == "()":
+        return f"({to_string(tree[1])}){count_str(tree[2])}"
+    return f"{to_string(tree[1])}{tree[0]}{to_string(tree[2])}"
+
+
+def matching_nodes(tree: tuple, node_features: Dict[str, Iterable[str]]) -> List[str]:
+    """
+    node_features: {hostname: features}
+    """
+    return sorted(x for x, y in node_features.items() if matches(tree, y))
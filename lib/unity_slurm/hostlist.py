@@ -0,0 +1,125 @@
+"""
+slurm hostlist expressions, like `gpu[001-016],cpu0[10-29]`, see HOSTLIST EXPRESSIONS in
+`man scontrol`. the numbers in brackets are ranges or single numbers separated by commas,
+and keep the zero padding of the first number in the range. a hostname can have more than
+one bracket, `rack[1-2]-node[01-02]` is every combination.
+"""
+import re
+import itertools
+from typing import Dict, Iterable, List, Tuple
+
+# the last number in a hostname, which is what gets compressed
+NUMBER_SUFFIX_REGEX = re.compile(r"(.*?)([0-9]+)")
+
+
+class HostlistError(ValueError):
+    pass
+
+
+def _split_top_level(hostlist: str) -> List[str]:
+    """
+    split on the commas that are not inside brackets
+    """
+    parts = []
+    depth = 0
+    current = ""
+    for char in hostlist:
+        if char == "[":
+            if depth > 0:
+                raise HostlistError(f'invalid hostlist "{hostlist}", brackets can\'t be nested')
+            depth += 1
+        elif char == "]":
+            if depth == 0:
+                raise HostlistError(f'invalid hostlist "{hostlist}", unexpected "]"')
+            depth -= 1
+        if char == "," and depth == 0:
+            parts.append(current)
+            current = ""
+        else:
+            current += char
+    if depth > 0:
+        raise HostlistError(f'invalid hostlist "{hostlist}", a "[" is not closed')
+    parts.append(current)
+    return [x.strip() for x in parts if x.strip() != ""]
+
+
+def _expand_brackets(hostlist: str, contents: str) -> List[str]:
+    """
+    "001-003,7" -> ["001", "002", "003", "7"]
+    """
+    output = []
+    for item in contents.split(","):
+        match = re.fullmatch(r"\s*([0-9]+)(?:-([0-9]+))?\s*", item)
+        if not match:
+            raise HostlistError(f'invalid hostlist "{hostlist}", expected a number, not "{item}"')
+        first, last = match.groups()
+        if last is None:
+            output.append(first)
+            continue
+        if int(last) < int(first):
+            raise HostlistError(f'invalid hostlist "{hostlist}", "{item}" goes backwards')
+        output += [str(x).zfill(len(first)) for x in range(int(first), int(last) + 1)]
+    return output
+
+
+def expand(hostlist: str) -> List[str]:
+    """
+    "cpu[001-003],gpu001" -> ["cpu001", "cpu002", "cpu003", "gpu001"], in the order given
+    raises HostlistError if the expression is not valid
+    """
+    output = []
+    for part in _split_top_level(hostlist):
+        # alternating literal text and bracket contents
+        pieces = re.split(r"\[([^\]]*)\]", part)
+        choices = [
+            [piece] if i % 2 == 0 else _expand_brackets(hostlist, piece)
+            for i, piece in enumerate(pieces)
+        ]
+        output += ["".join(x) for x in itertools.product(*choices)]
+    return output
+
+
+def expand_all(hostlists: Iterable[str]) -> List[str]:
+    """
+    expand each hostlist and leave out duplicates
+    """
+    output = []
+    for hostlist in hostlists:
+        output += [x for x in expand(hostlist) if x not in output]
+    return output
+
+
+def _fmt_ranges(numbers: List[int], width: int) -> str:
+    ranges = []
+    for number in sorted(set(numbers)):
+        if ranges and number == ranges[-1][1] + 1:
+            ranges[-1][1] = number
+        else:
+            ranges.append([number, number])
+    return ",".join(
+        str(first).zfill(width) if first == last else f"{first:0{width}}-{last:0{width}}"
+        for first, last in ranges
+    )
+
+
+def compress(hostnames: Iterable[str]) -> str:
+    """
+    ["cpu001", "cpu002", "cpu003", "gpu001"] -> "cpu[001-003],gpu001", sorted
+    hostnames that end in numbers of a different width are not put in the same brackets
+    """
+    groups: Dict[Tuple[str, int], List[int]] = {}
+    others = set()
+    for hostname in hostnames:
+        match = NUMBER_SUFFIX_REGEX.fullmatch(hostname)
+        if match is None:
+            others.add(hostname)
+            continue
+        prefix, digits = match.groups()
+        groups.setdefault((prefix, len(digits)), []).append(int(digits))
+    output = sorted(others)
+    for (prefix, width), numbers in sorted(groups.items()):
+        if len(set(numbers)) == 1:
+            output.append(f"{prefix}{numbers[0]:0{width}}")
+        else:
+            output.append(f"{prefix}[{_fmt_ranges(numbers, width)}]")
+    return ",".join(output)
@@ -0,0 +1,227 @@
+"""
+the association and QOS limits that would keep a job from starting
+
+slurm checks the GrpTRES of the job's association and of every account above it, and the
+GrpTRES, MaxTRESPerUser and MaxTRES (per job) of the job's QOS. only running jobs count
+towards the group and per user limits. only the TRES that the job asks for are checked.
+used by unity-compute and unity-slurm-account-usage.
+"""
+from typing import Dict, List, Optional, Tuple
+
+from unity_slurm import config, slurm, schema, tres
+
+# usage in preempt partitions doesn't count towards account limits
+IGNORE_PARTITIONS = config.get("preempt_partitions")
+
+# the squeue reason that a job waiting on each kind of limit is given
+REASONS = {
+    "assoc_grp": {
+        "cpu": "AssocGrpCpuLimit",
+        "mem": "AssocGrpMemLimit",
+        "node": "AssocGrpNodeLimit",
+        "gres": "AssocGrpGRES",
+    },
+    "qos_grp": {
+        "cpu": "QOSGrpCpuLimit",
+        "mem": "QOSGrpMemLimit",
+        "node": "QOSGrpNodeLimit",
+        "gres": "QOSGrpGRES",
+    },
+    "qos_per_user": {
+        "cpu": "QOSMaxCpuPerUserLimit",
+        "mem": "QOSMaxMemoryPerUser",
+        "node": "QOSMaxNodePerUserLimit",
+        "gres": "QOSMaxGRESPerUser",
+    },
+    "qos_per_job": {
+        "cpu": "QOSMaxCpuPerJobLimit",
+        "mem": "QOSMaxMemoryPerJob",
+        "node": "QOSMaxNodePerJobLimit",
+        "gres": "QOSMaxGRESPerJob",
+    },
+}
+# the other TRES, like billing, license/x and energy, are named the same way in each kind
+OTHER_REASONS = {
+    "assoc_grp": "AssocGrp{}",
+    "qos_grp": "QOSGrp{}",
+    "qos_per_user": "QOSMax{}PerUser",
+    "qos_per_job": "QOSMax{}PerJob",
+}
+
+
+def all_associations(timeout: Optional[float] = None) -> List[dict]:
+    """
+    everyone's associations, the limits of an account apply to all of its users
+    timeout: seconds to wait for slurmdbd, raises subprocess.TimeoutExpired after that
+    """
+    return schema.normalize_associations(
+        slurm.slurm_json(
+            "sacctmgr-associations",
+            [slurm.command("sacctmgr"), "show", "association", "--json"],
+            timeout=timeout,
+        )
+    )
+
+
+def all_qos() -> List[dict]:
+    return schema.normalize_qos_list(
+        slurm.slurm_json("sacctmgr-qos", [slurm.command("sacctmgr"), "show", "qos", "--json"])
+    )
+
+
+def default_account(associations: List[dict], user: str) -> Optional[str]:
+    for association in associations:
+        if association["user"] == user and association["is_default"]:
+            return association["account"]
+    return None
+
+
+def default_qos(associations: List[dict], user: str, account: str) -> Optional[str]:
+    """
+    the association's default QOS, or its only QOS if it has one
+    """
+    for association in associations:
+        if association["user"] == user and association["account"] == account:
+            if association["default_qos"]:
+                return association["default_qos"]
+            if len(association["qos"]) == 1:
+                return association["qos"][0]
+    return None
+
+
+def _account_chain(associations: List[dict], user: str, account: str) -> List[dict]:
+    """
+    the user's association in this account, then the account, then each parent account
+    """
+    chain = [x for x in associations if x["user"] == user and x["account"] == account][:1]
+    account_associations = {x["account"]: x for x in associations if x["user"] == ""}
+    while account in account_associations and account not in [x["account"] for x in chain[1:]]:
+        chain.append(account_associations[account])
+        account = account_associations[account]["parent_account"]
+    return chain
+
+
+def _sub_accounts(associations: List[dict], account: str) -> List[str]:
+    children: Dict[str, List[str]] = {}
+    for association in associations:
+        if association["user"] == "":
+            children.setdefault(association["parent_account"], []).append(association["account"])
+    output, todo = [], [account]
+    while todo:
+        x = todo.pop()
+        if x not in output:
+            output.append(x)
+            todo += children.get(x, [])
+    return output
+
+
+def _tres_used(jobs: List[dict]) -> Dict[str, int]:
+    used: Dict[str, int] = {}
+    for job in jobs:
+        for name, count in tres.parse(job["tres_alloc_str"]).items():
+            used[name] = used.get(name, 0) + count
+    return used
+
+
+def _reason(kind: str, name: str) -> str:
+    """
+    "AssocGrpCpuLimit" for ("assoc_grp", "cpu"), "AssocGrpBilling" for ("assoc_grp", "billing")
+    """
+    base = name.split("/")[0]
+    return REASONS[kind].get(base, OTHER_REASONS[kind].format(base.capitalize()))
+
+
+def _check(kind, scope, limit_tres, used_tres, request_tres) -> List[dict]:
+    output = []
+    for name, limit in sorted(limit_tres.items()):
+        if name not in request_tres:
+            continue
+        used = used_tres.get(name, 0)
+        if used + request_tres[name] > limit:
+            output.append(
+                {
+                    "reason": _reason(kind, name),
+                    "scope": scope,
+                    "tres": name,
+                    "limit": limit,
+                    "used": used,
+                    "requested": request_tres[name],
+                    # a per job limit is never lifted, the others are once running jobs end
+                    "waits": kind != "qos_per_job" and request_tres[name] <= limit,
+                }
+            )
+    return output
+
+
+def limit_usage(
+    user: str,
+    account: str,
+    qos_name: Optional[str],
+    jobs: List[dict],
+    associations: List[dict],
+    qos_list: List[dict],
+    partition: Optional[str] = None,
+) -> List[Tuple[str, str, Dict[str, int], Dict[str, int]]]:
+    """
+    the limits that apply to a job of this user, account and QOS, and how much of each
+    is used by running jobs. returns [(kind, scope, limit TRES, used TRES)], kind is a key
+    in REASONS. jobs in preempt partitions don't count towards the association limits,
+    and a job in a preempt partition is not held back by them.
+    """
+    running = [x for x in jobs if schema.job_has_state(x, "RUNNING")]
+    output = []
+    if partition not in IGNORE_PARTITIONS:
+        counted_running = [x for x in running if x["partition"] not in IGNORE_PARTITIONS]
+        for association in _account_chain(associations, user, account):
+            if association["user"] != "":
+                scope = f'user "{user}" in account "{association["account"]}"'
+                counted = [
+                    x for x in counted_running if x["account"] == account and x["user_name"] == user
+                ]
+            else:
+                scope = f'account "{association["account"]}"'
+                sub_accounts = _sub_accounts(associations, association["account"])
+                counted = [x for x in counted_running if x["account"] in sub_accounts]
+            output.append(("assoc_grp", scope, association["grp_tres"], _tres_used(counted)))
+    qos = next((x for x in qos_list if x["name"] == qos_name), None)
+    if qos is not None:
+        qos_jobs = [x for x in running if x["qos"] == qos_name]
+        user_qos_jobs = [x for x in qos_jobs if x["user_name"] == user]
+        output += [
+            ("qos_grp", f'QOS "{qos_name}"', qos["grp_tres"], _tres_used(qos_jobs)),
+            (
+                "qos_per_user",
+                f'each user in QOS "{qos_name}"',
+                qos["max_tres_per_user"],
+                _tres_used(user_qos_jobs),
+            ),
+            ("qos_per_job", f'each job in QOS "{qos_name}"', qos["max_tres_per_job"], {}),
+        ]
+    return output
+
+
+def blocking_limits(request_tres: Dict[str, int], *args, **kwargs) -> List[dict]:
+    """
+    request_tres: {"cpu": 4, "mem": 4096, "node": 1, "gres/gpu": 1}, memory in MB
+    the other arguments are the same as for limit_usage
+    returns the limits that this job would exceed if it were submitted now
+    """
+    output = []
+    for kind, scope, limit_tres, used_tres in limit_usage(*args, **kwargs):
+        output += _check(kind, scope, limit_tres, used_tres, request_tres)
+    return output
+
+
+def job_request_tres(job: dict) -> Dict[str, int]:
+    """
+    what a pending job asks for, in the same form as request_tres
+    """
+    return tres.parse(job["tres_req_str"])
+
+
+def describe(limit: dict) -> str:
+    name = limit["tres"]
+    line = f"{limit['reason']}: {limit['scope']} is limited to {tres.format_tres(name, limit['limit'])}"
+    if limit["used"] > 0:
+        line += f", running jobs use {tres.format_tres(name, limit['used'])}"
+    return line + f" and this job asks for {tres.format_tres(name, limit['requested'])}"
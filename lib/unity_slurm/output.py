@@ -0,0 +1,187 @@
+"""
+machine readable output for the usage reports, see docs/output-formats.md
+
+"table" is the usual human readable output and is handled by each command, with fmt_table.
+the other formats print one record per row, with raw numbers and no ANSI codes.
+"""
+import io
+import os
+import csv
+import sys
+import json
+from typing import List, Optional
+
+# increment when a field is renamed, removed, or changes meaning. adding a field is fine.
+SCHEMA_VERSION = 1
+
+FORMATS = ["table", "json", "csv", "tsv"]
+FORMAT_ARG = "--format"
+# set by `unity-slurm --format`
+FORMAT_ENV_VAR = "UNITY_SLURM_FORMAT"
+
+ANSI_UNDERLINE = "\033[4m"
+ANSI_BRIGHT = "\033[0;1m"
+ANSI_RESET = "\033[0m"
+
+
+def default_format() -> str:
+    fmt = os.environ.get(FORMAT_ENV_VAR) or "table"
+    if fmt not in FORMATS:
+        sys.exit(f'{FORMAT_ENV_VAR}="{fmt}" is not one of {FORMATS}')
+    return fmt
+
+
+def pop_format_arg(argv: List[str]) -> str:
+    """
+    remove `--format FORMAT` or `--format=FORMAT` from argv (in place) and return FORMAT
+    """
+    fmt = default_format()
+    for i, arg in enumerate(argv):
+        if arg == FORMAT_ARG:
+            if i + 1 >= len(argv):
+                sys.exit(f"{FORMAT_ARG} requires an argument, one of {FORMATS}")
+            fmt = argv[i + 1]
+            del argv[i : i + 2]
+            break
+        if arg.startswith(FORMAT_ARG + "="):
+            fmt = arg.split("=", 1)[1]
+            del argv[i]
+            break
+    if fmt not in FORMATS:
+        sys.exit(f'invalid format "{fmt}", expected one of {FORMATS}')
+    return fmt
+
+
+def _column_widths(table, between_column_padding_size: int) -> List[int]:
+    # no row has more elements than the header row
+    assert all(len(row) <= len(table[0]) for row in table)
+    column_widths = [0] * len(table[0])
+    for row in table:
+        for i, element in enumerate(row):
+            if len(str(element)) > column_widths[i]:
+                column_widths[i] = len(str(element))
+    return [x + between_column_padding_size for x in column_widths]
+
+
+def _fmt_header(table, column_widths: List[int]) -> str:
+    header = ""
+    for i, column_header in enumerate(table[0]):
+        if i > 0:
+            header += "|"
+        header += str(column_header).center(column_widths[i] - 1)  # minus one for the '|'
+    return header
+
+
+def fmt_table(
+    table, between_column_padding_size=5, alternate_brightness=False, left_padding_size=0
+) -> List[str]:
+    """
+    the first row of table is the header, it is underlined with "="
+    I would use tabulate but I don't want nonstandard imports
+    """
+    column_widths = _column_widths(table, between_column_padding_size)
+    header = _fmt_header(table, column_widths)
+    output_lines = [header, "=" * len(header)]
+    for row in table[1:]:
+        line = " " * left_padding_size
+        for i, value in enumerate(row):
+            line = line + str(value).ljust(column_widths[i])
+        output_lines.append(line)
+    if alternate_brightness:
+        for i, line in enumerate(output_lines):
+            if i <= 1:
+                continue  # skip the header
+            if i % 2 == 0:
+                output_lines[i] = ANSI_BRIGHT + line + ANSI_RESET
+    return output_lines
+
+
+def fmt_underlined_table(table) -> str:
+    """
+    like fmt_table but denser, with an ANSI underlined header, one line per row
+    """
+    column_widths = _column_widths(table, 3)  # room for whitespace on either side
+    table_output = ANSI_UNDERLINE + _fmt_header(table, column_widths) + ANSI_RESET + "\n"
+    for row in table[1:]:
+        for i, value in enumerate(row):
+            table_output += str(value).ljust(column_widths[i])
+        table_output += "\n"
+    return table_output
+
+
+def closest_element_index(_list, target) -> int:
+    """
+    return the index of the list element which is closest to target
+    """
+    min_diff = None
+    min_diff_index = -1
+    for i, element in enumerate(_list):
+        diff = element - target
+        if i == 0 or abs(diff) < abs(min_diff):
+            min_diff = diff
+            min_diff_index = i
+    return min_diff_index
+
+
+def generate_progress_bar(frac: float, _len=15, fill_char="#") -> str:
+    """
+    a bar is never empty unless frac is 0, and never full unless frac is 1
+    """
+    if frac < 0:
+        frac = 0
+    if frac > 1:
+        frac = 1
+    _len -= 2  # subtract beginning and end characters
+    num_chars2frac = [x / _len for x in range(_len + 1)]  # [ 0, 1/len, 2/len, ... len/len=1 ]
+    num_chars = closest_element_index(
+        num_chars2frac, frac
+    )  # round `frac` to the nearest character length fraction
+    if num_chars == 0 and frac > 0:
+        num_chars = 1
+    if num_chars == _len and frac < 1:
+        num_chars = _len - 1
+    progress_bar = "[" + (fill_char * num_chars) + (" " * (_len - num_chars)) + "]"
+    return progress_bar
+
+
+def _flatten(value) -> str:
+    if isinstance(value, (list, tuple, set)):
+        return ",".join(str(x) for x in value)
+    if isinstance(value, bool):
+        return str(value).lower()
+    if value is None:
+        return ""
+    return str(value)
+
+
+def format_records(
+    report: str, fields: List[str], records: List[dict], fmt: str, extra: Optional[dict] = None
+) -> str:
+    """
+    report: name of the report, for the JSON envelope
+    fields: column order for csv/tsv. every record must have exactly these keys.
+    extra: more top level keys for the JSON envelope, ignored by csv/tsv
+    """
+    for record in records:
+        assert list(record.keys()) == fields, f"{list(record.keys())} != {fields}"
+    if fmt == "json":
+        envelope = {"schema_version": SCHEMA_VERSION, "report": report, "records": records}
+        if extra is not None:
+            envelope.update(extra)
+        return json.dumps(envelope, indent=2)
+    if fmt in ["csv", "tsv"]:
+        buffer = io.StringIO()
+        writer = csv.writer(
+            buffer, delimiter="," if fmt == "csv" else "\t", lineterminator="\n"
+        )
+        writer.writerow(fields)
+        for record in records:
+            writer.writerow([_flatten(record[x]) for x in fields])
+        return buffer.getvalue().rstrip("\n")
+    raise ValueError(f'format "{fmt}" is not machine readable')
+
+
+def print_records(
+    report: str, fields: List[str], records: List[dict], fmt: str, extra: Optional[dict] = None
+) -> None:
+    print(format_records(report, fields, records, fmt, extra))
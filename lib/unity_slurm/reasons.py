@@ -0,0 +1,114 @@
+"""
+plain language explanations of the reasons that squeue gives for a job pending
+
+see JOB REASON CODES in `man squeue`. the limit reasons come in families, like
+AssocGrpCpuLimit, AssocGrpMemLimit and AssocGrpGRES, so they are matched by pattern.
+"""
+import re
+from typing import List, Tuple
+
+from unity_slurm import config
+
+# (pattern, what it means, what to do about it), the first pattern that matches is used
+EXPLANATIONS: List[Tuple[str, str, str]] = [
+    (
+        r"None",
+        "the job was submitted moments ago and the scheduler hasn't looked at it yet",
+        "wait a minute",
+    ),
+    (
+        r"Priority",
+        "jobs with a higher priority are waiting for the same nodes and will start first",
+        "wait, or see `unity-slurm fairshare` for where the job ranks in its partition",
+    ),
+    (
+        r"Resources",
+        "the job is next in line, and is waiting for enough nodes to become free",
+        "wait, or ask for fewer resources or a shorter time limit so that it fits sooner",
+    ),
+    (
+        r"Dependency",
+        "the job is waiting for the jobs in its --dependency to finish",
+        "wait, `squeue --job=<id>` shows the dependency",
+    ),
+    (
+        r"DependencyNeverSatisfied",
+        "a job that this one depends on failed or was cancelled, so it can never start",
+        "cancel it with `scancel` and submit it again",
+    ),
+    (
+        r"BeginTime",
+        "the job's --begin time hasn't come yet",
+        "wait, or change it with `scontrol update job=<id> StartTime=now`",
+    ),
+    (
+        r"JobHeldUser",
+        "the job was held with `scontrol hold`",
+        "release it with `scontrol release <id>`",
+    ),
+    (
+        r"JobHeldAdmin|launch failed requeued held",
+        "the job was held by an administrator or after it failed to launch",
+        "ask the helpdesk why it was held",
+    ),
+    (
+        r"ReqNodeNotAvail.*|Reservation|Maint.*",
+        "the nodes that the job needs are down, drained or reserved, often for maintenance",
+        "lower the time limit so that the job ends before the maintenance, or remove --nodelist",
+    ),
+    (
+        r"PartitionTimeLimit",
+        "the job's time limit is longer than the partition allows, so it can never start",
+        "lower it with `scontrol update job=<id> TimeLimit=...`, or use another partition",
+    ),
+    (
+        r"PartitionNodeLimit|PartitionConfig|BadConstraints",
+        "no node in the partition has what the job asks for, so it can never start",
+        "check --constraint with `unity-slurm list-constraints`, or use another partition",
+    ),
+    (
+        r"InvalidAccount|InvalidQOS",
+        "the job's account or QOS is not one that you can use, so it can never start",
+        "cancel it and submit it again with an account from `unity-slurm account-list`",
+    ),
+    (
+        r"(Assoc|QOS)Max.*PerJob.*",
+        "the job asks for more than one job is allowed to have, so it can never start",
+        "cancel it and submit it again asking for less, see `unity-slurm account-list`",
+    ),
+    (
+        r"AssocMax(Jobs|SubmitJob)Limit|QOSMax(Jobs|SubmitJob)PerUserLimit",
+        "you already have the most jobs that you are allowed to have at once",
+        "wait for some of your jobs to finish",
+    ),
+    (
+        r"AssocGrp.*",
+        "the jobs that are running under the account add up to one of its limits",
+        "wait for running jobs to finish, or use a preempt partition ({preempt}), "
+        "which doesn't count towards account limits",
+    ),
+    (
+        r"QOSMax.*PerUser.*",
+        "your running jobs in this QOS add up to the limit for each user",
+        "wait for your running jobs to finish",
+    ),
+    (
+        r"QOSGrp.*",
+        "the jobs that are running in this QOS, from every account, add up to its limit",
+        "wait for running jobs to finish, or use another partition",
+    ),
+]
+UNKNOWN_EXPLANATION = (
+    "there is no explanation for this reason here",
+    "see JOB REASON CODES in `man squeue`",
+)
+
+
+def explain(reason: str) -> Tuple[str, str]:
+    """
+    returns (what it means, what to do about it)
+    """
+    for pattern, meaning, fix in EXPLANATIONS:
+        if re.fullmatch(pattern, reason):
+            return meaning, fix.format(preempt=", ".join(config.get("preempt_partitions")))
+    return UNKNOWN_EXPLANATION
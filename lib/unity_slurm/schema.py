@@ -0,0 +1,335 @@
+"""
+map the JSON output of different slurm data_parser versions onto one model
+
+the commands should only read the fields documented in normalize_job, normalize_sinfo_node,
+normalize_association, normalize_account, normalize_qos, normalize_sacct_job and
+normalize_share, so that a slurm upgrade changes this file and nothing else.
+
+differences handled here:
+* job_state: "RUNNING" in v0.0.39, ["RUNNING"] in v0.0.40 and later
+* numbers: plain ints in some places, {"set": ..., "infinite": ..., "number": ...} in others
+* job_resources: "allocated_nodes" in v0.0.39 and v0.0.40, "nodes": {"allocation": ...} in
+  v0.0.41 and later
+* socket and core maps: dicts keyed by index in v0.0.39, lists in v0.0.40 and later
+* node state: a string in older versions, a list of flags in newer ones
+* association qos: a list of names, or a comma separated string
+* TRES limits: lists of {"type": "gres", "name": "gpu", "count": 4}, unset limits are left out
+"""
+import re
+from typing import List, Optional, Tuple
+
+
+def number(x, default=0) -> int:
+    """
+    v0.0.39 and later wrap most numbers: {"set": true, "infinite": false, "number": 5}
+    an unset or infinite number becomes `default`
+    """
+    if isinstance(x, dict):
+        if not x.get("set", True) or x.get("infinite", False):
+            return default
+        return x.get("number", default)
+    if x is None:
+        return default
+    return x
+
+
+def flag_list(x) -> List[str]:
+    if x is None:
+        return []
+    if isinstance(x, str):
+        return [y.strip() for y in x.split(",") if y.strip() != ""]
+    return list(x)
+
+
+def _count_allocated_cores(sockets) -> int:
+    """
+    v0.0.39: {"0": {"cores": {"0": "allocated", ...}}, ...}
+    v0.0.40 and later: [{"index": 0, "cores": [{"index": 0, "status": ["ALLOCATED"]}, ...]}, ...]
+    """
+    if isinstance(sockets, dict):
+        sockets = list(sockets.values())
+    num_cores = 0
+    for socket in sockets:
+        cores = socket["cores"]
+        if isinstance(cores, dict):
+            statuses = [flag_list(x) for x in cores.values()]
+        else:
+            statuses = [flag_list(x.get("status", "allocated")) for x in cores]
+        for status in statuses:
+            if "UNALLOCATED" not in [x.upper() for x in status]:
+                num_cores += 1
+    return num_cores
+
+
+def _allocated_nodes(job_resources: dict) -> List[dict]:
+    output = []
+    if "allocated_nodes" in job_resources:  # v0.0.39, v0.0.40
+        for node in job_resources["allocated_nodes"]:
+            if "sockets" in node:
+                cpus = _count_allocated_cores(node["sockets"])
+            else:
+                cpus = number(node.get("cpus"))
+            output.append(
+                {
+                    "nodename": node["nodename"],
+                    "cpus": cpus,
+                    "memory_allocated": number(node.get("memory_allocated")),
+                }
+            )
+    elif isinstance(job_resources.get("nodes"), dict):  # v0.0.41 and later
+        for node in job_resources["nodes"].get("allocation", []):
+            if node.get("sockets"):
+                cpus = _count_allocated_cores(node["sockets"])
+            else:
+                cpus = number(node.get("cpus", {}).get("count"))
+            output.append(
+                {
+                    "nodename": node["name"],
+                    "cpus": cpus,
+                    "memory_allocated": number(node.get("memory", {}).get("allocated")),
+                }
+            )
+    return output
+
+
+def normalize_job(job: dict) -> dict:
+    """
+    one element of `squeue --json`["jobs"]
+    job_state is always a list, numbers are always ints, times are unix timestamps (0 if unset)
+    allocated_nodes: [{"nodename": str, "cpus": int, "memory_allocated": int (MB)}]
+    """
+    return {
+        "job_id": number(job["job_id"]),
+        "name": job.get("name", ""),
+        "user_name": job["user_name"],
+        "account": job["account"],
+        "partition": job["partition"],
+        "qos": job.get("qos", "") or "",
+        "job_state": flag_list(job["job_state"]),
+        "state_reason": job.get("state_reason", ""),
+        "cpus": number(job.get("cpus")),
+        "node_count": number(job.get("node_count")),
+        "nodes": job.get("nodes", ""),
+        "tres_alloc_str": job.get("tres_alloc_str", ""),
+        "tres_req_str": job.get("tres_req_str", ""),
+        "allocated_nodes": _allocated_nodes(job.get("job_resources") or {}),
+        "batch_flag": job.get("batch_flag", True),
+        "submit_time": number(job.get("submit_time")),
+        "start_time": number(job.get("start_time")),
+        "end_time": number(job.get("end_time")),
+        "time_limit": number(job.get("time_limit")),  # minutes, 0 if unlimited
+    }
+
+
+def normalize_squeue(squeue: dict) -> dict:
+    return {"jobs": [normalize_job(x) for x in squeue["jobs"]]}
+
+
+def job_has_state(job: dict, state: str) -> bool:
+    """
+    case insensitive
+    """
+    return state.upper() in [x.upper() for x in job["job_state"]]
+
+
+def normalize_partition(partition: dict) -> dict:
+    def allow_deny(key: str) -> dict:
+        x = partition.get(key) or {}
+        return {"allowed": x.get("allowed", "") or "", "deny": x.get("deny", "") or ""}
+
+    return {
+        "name": partition["name"],
+        "accounts": allow_deny("accounts"),
+        "qos": allow_deny("qos"),
+        "groups": allow_deny("groups"),
+        # None if unlimited
+        "max_time_minutes": number((partition.get("maximums") or {}).get("time"), default=None),
+    }
+
+
+def normalize_sinfo_node(sinfo_element: dict) -> dict:
+    """
+    one element of `sinfo --json`["sinfo"] or `sinfo -N --json`["sinfo"]
+    """
+    features = sinfo_element.get("features") or {}
+    return {
+        "nodes": list(sinfo_element["nodes"]["nodes"]),
+        "state": [x.upper() for x in flag_list(sinfo_element["node"]["state"])],
+        "cpus": number(sinfo_element["cpus"]["maximum"]),
+        "memory_MB": number(sinfo_element["memory"]["maximum"]),
+        "gres": sinfo_element["gres"]["total"] or "",
+        "features_total": flag_list(features.get("total")),
+        "features_active": flag_list(features.get("active")),
+        "partition": normalize_partition(sinfo_element["partition"]),
+    }
+
+
+def normalize_sinfo(sinfo: dict) -> List[dict]:
+    return [normalize_sinfo_node(x) for x in sinfo["sinfo"]]
+
+
+def normalize_association(association: dict) -> dict:
+    """
+    one element of `sacctmgr show association --json`["associations"]
+    the original is kept under "raw" for the fields that are not normalized yet
+    """
+    tres = (association.get("max") or {}).get("tres") or {}
+    jobs = (association.get("max") or {}).get("jobs") or {}
+    return {
+        "account": association.get("account", ""),
+        "user": association.get("user", ""),
+        "partition": association.get("partition", "") or "",
+        "qos": flag_list(association.get("qos")),
+        "default_qos": (association.get("default") or {}).get("qos", "") or "",
+        "is_default": bool(association.get("is_default", False)),
+        "parent_account": association.get("parent_account", "") or "",
+        "shares": number(association.get("shares_raw"), default=None),
+        # GrpTRES
+        "grp_tres": tres_dict(tres.get("total")),
+        # MaxTRES
+        "max_tres_per_job": tres_dict((tres.get("per") or {}).get("job")),
+        # MaxJobs and MaxSubmit, None if unlimited
+        "max_jobs": number(jobs.get("active"), default=None),
+        "max_submit": number(jobs.get("total"), default=None),
+        "raw": association,
+    }
+
+
+def normalize_associations(sacctmgr: dict) -> List[dict]:
+    return [normalize_association(x) for x in sacctmgr["associations"]]
+
+
+def normalize_account(account: dict) -> dict:
+    """
+    one element of `sacctmgr show account withcoord --json`["accounts"]
+    """
+    return {
+        "name": account["name"],
+        "description": account.get("description", "") or "",
+        "organization": account.get("organization", "") or "",
+        "coordinators": [x["name"] for x in account.get("coordinators") or []],
+    }
+
+
+def normalize_accounts(sacctmgr: dict) -> List[dict]:
+    return [normalize_account(x) for x in sacctmgr["accounts"]]
+
+
+def normalize_qos(qos: dict) -> dict:
+    """
+    one element of `sacctmgr show qos --json`["qos"]
+    """
+    tres = ((qos.get("limits") or {}).get("max") or {}).get("tres") or {}
+    per = tres.get("per") or {}
+    return {
+        "name": qos["name"],
+        "grp_tres": tres_dict(tres.get("total")),  # GrpTRES
+        "max_tres_per_user": tres_dict(per.get("user")),  # MaxTRESPerUser
+        "max_tres_per_job": tres_dict(per.get("job")),  # MaxTRES
+    }
+
+
+def normalize_qos_list(sacctmgr: dict) -> List[dict]:
+    return [normalize_qos(x) for x in sacctmgr["qos"]]
+
+
+def normalize_sacct_job(job: dict) -> dict:
+    """
+    one element of `sacct --json`["jobs"]
+    start is 0 if the job never started, end is 0 if it is still running
+    tres: what was allocated, in the same form as tres_dict
+    """
+    time = job.get("time") or {}
+    return {
+        "job_id": number(job["job_id"]),
+        "user": job.get("user", ""),
+        "account": job.get("account", ""),
+        "partition": job.get("partition", ""),
+        "start": number(time.get("start")),
+        "end": number(time.get("end")),
+        "tres": tres_dict((job.get("tres") or {}).get("allocated")),
+    }
+
+
+def normalize_sacct(sacct: dict) -> List[dict]:
+    return [normalize_sacct_job(x) for x in sacct["jobs"]]
+
+
+def normalize_share(share: dict) -> dict:
+    """
+    one element of `sshare --json`["shares"]["shares"], an account or a user in an account
+    user is "" for an account. numbers are None if slurm doesn't set them.
+    """
+    is_user = "USER" in [x.upper() for x in flag_list(share.get("type"))]
+    fairshare = share.get("fairshare") or {}
+    return {
+        "account": share.get("parent", "") if is_user else share["name"],
+        "user": share["name"] if is_user else "",
+        "parent": share.get("parent", "") or "",
+        "raw_shares": number(share.get("shares"), default=None),
+        "norm_shares": number(share.get("shares_normalized"), default=None),
+        "raw_usage": number(share.get("usage"), default=None),
+        "norm_usage": number(share.get("usage_normalized"), default=None),
+        "effective_usage": number(share.get("effective_usage"), default=None),
+        "fairshare_factor": number(fairshare.get("factor"), default=None),
+    }
+
+
+def normalize_sshare(sshare: dict) -> List[dict]:
+    return [normalize_share(x) for x in sshare["shares"]["shares"]]
+
+
+def tres_dict(tres_list) -> dict:
+    """
+    [{"type": "cpu", "name": "", "count": 32}, {"type": "gres", "name": "gpu", "count": 4}]
+    -> {"cpu": 32, "gres/gpu": 4}, the same names as in a tres string
+    """
+    output = {}
+    for tres in tres_list or []:
+        name = f"{tres['type']}/{tres['name']}" if tres.get("name") else tres["type"]
+        count = number(tres.get("count"), default=None)
+        if count is not None and count >= 0:
+            output[name] = count
+    return output
+
+
+def parse_gres_gpus(gres: str) -> Optional[Tuple[str, int]]:
+    """
+    find the GPUs in a node's gres string, return (gpu_type, gpu_count) or None
+    gpu_type is "" if the node's GPUs have no type
+    example: "gpu:2080_ti:8(S:0-1),shard:8" -> ("2080_ti", 8)
+    """
+    gpu_resources = [x for x in gres.split(",") if x.startswith("gpu:")]
+    assert len(gpu_resources) <= 1, "there must be at most 1 GPU resource specified per node"
+    if len(gpu_resources) == 0:
+        return None
+    # https://github.com/SchedMD/slurm/blob/51b5f5bcb8704a56cc58c56f02cb81bb3346636d/src/interfaces/gres.c#L4361
+    resource = re.sub(r"\(S:[^)]*\)$", "", gpu_resources[0])
+    parts = resource.split(":")
+    if len(parts) == 2:  # gpu:4
+        return "", int(parts[1])
+    _, gpu_type, gpu_count = parts
+    return gpu_type, int(gpu_count)
+
+
+def tres_gpus(tres_str: str) -> Tuple[int, dict]:
+    """
+    count the GPUs in a job's tres_alloc_str or tres_req_str
+    returns (total, {gpu_type: count}). total can be more than the sum of the types,
+    when some of the GPUs were requested without a type.
+    example: "cpu=4,mem=40G,node=1,billing=1,gres/gpu=1,gres/gpu:2080ti=1"
+    """
+    total = 0
+    by_type = {}
+    for resource in tres_str.split(","):
+        generic_match = re.fullmatch(r"gres/gpu=(\d+)", resource)
+        if generic_match:
+            total += int(generic_match.group(1))
+            continue
+        specific_match = re.fullmatch(r"gres/gpu:([^=]+)=(\d+)", resource)
+        if specific_match:
+            gpu_type, gpu_count = specific_match.groups()
+            by_type[gpu_type] = by_type.get(gpu_type, 0) + int(gpu_count)
+    # some requests list only the typed GPUs
+    total = max(total, sum(by_type.values()))
+    return total, by_type
@@ -0,0 +1,221 @@
+"""
+get slurm JSON output, either by running the slurm command or by reading a snapshot
+
+a snapshot is a directory with one file per query, named as in SNAPSHOT_FILES.
+unity-slurm-snapshot also writes MANIFEST_FILE, which says who took the snapshot.
+it is selected with the `--from-snapshot DIR` argument or the UNITY_SLURM_SNAPSHOT
+environment variable, and then no slurm commands are run at all.
+"""
+import os
+import grp
+import copy
+import sys
+import json
+import time
+import datetime
+import functools
+import subprocess as subp  # nosec
+from typing import Dict, List, Optional
+
+# the test suite points this at stub slurm commands
+SLURM_BIN_DIR = os.getenv("UNITY_SLURM_BIN_DIR", "/usr/bin")
+
+# set by `unity-slurm --user`
+USER_ENV_VAR = "UNITY_SLURM_USER"
+
+SNAPSHOT_ENV_VAR = "UNITY_SLURM_SNAPSHOT"
+SNAPSHOT_ARG = "--from-snapshot"
+MANIFEST_FILE = "manifest.json"
+
+SNAPSHOT_FILES = {
+    "sinfo": "sinfo.json",  # sinfo --all --json
+    "sinfo-N": "sinfo-N.json",  # sinfo --all -N --json
+    "squeue": "squeue.json",  # squeue --all --json
+    "sacctmgr-associations": "sacctmgr-associations.json",  # sacctmgr show association --json
+    "sacctmgr-qos": "sacctmgr-qos.json",  # sacctmgr show qos --json
+    "sacctmgr-accounts": "sacctmgr-accounts.json",  # sacctmgr show account withcoord --json
+    "sacct": "sacct.json",  # sacct --allusers --json
+    "scontrol-nodes": "scontrol-nodes.json",  # scontrol --json show nodes
+    "sshare": "sshare.json",  # sshare --all --json
+    "sprio": "sprio.txt",  # sprio --noheader --format=SPRIO_FORMAT, sprio has no --json
+}
+
+# job id, partition, user, priority, then the weighted age, fairshare, partition, QOS and
+# job size factors, which add up to the priority
+SPRIO_FORMAT = "%i|%r|%u|%Y|%A|%F|%P|%Q|%J"
+
+
+# when the data for each query in SNAPSHOT_FILES was collected, as a unix timestamp
+data_times: Dict[str, float] = {}
+
+
+def command(name: str) -> str:
+    """
+    absolute path to a slurm command
+    """
+    return os.path.join(SLURM_BIN_DIR, name)
+
+
+def pop_snapshot_arg(argv: List[str]) -> None:
+    """
+    remove `--from-snapshot DIR` or `--from-snapshot=DIR` from argv (in place)
+    and export DIR so that child processes replay the same snapshot
+    """
+    for i, arg in enumerate(argv):
+        if arg == SNAPSHOT_ARG:
+            if i + 1 >= len(argv):
+                sys.exit(f"{SNAPSHOT_ARG} requires a directory argument")
+            use_snapshot(argv[i + 1])
+            del argv[i : i + 2]
+            return
+        if arg.startswith(SNAPSHOT_ARG + "="):
+            use_snapshot(arg.split("=", 1)[1])
+            del argv[i]
+            return
+
+
+def use_snapshot(snapshot_dir: Optional[str]) -> None:
+    if snapshot_dir is None:
+        return
+    if not os.path.isdir(snapshot_dir):
+        sys.exit(f'snapshot directory not found: "{snapshot_dir}"')
+    os.environ[SNAPSHOT_ENV_VAR] = os.path.abspath(snapshot_dir)
+
+
+def snapshot_dir() -> Optional[str]:
+    return os.environ.get(SNAPSHOT_ENV_VAR) or None
+
+
+def read_snapshot_file(name: str) -> str:
+    path = os.path.join(snapshot_dir(), SNAPSHOT_FILES[name])
+    try:
+        with open(path, "r", encoding="utf8") as file:
+            return file.read()
+    except FileNotFoundError:
+        sys.exit(f'snapshot "{snapshot_dir()}" does not contain "{SNAPSHOT_FILES[name]}"')
+
+
+def snapshot_manifest() -> dict:
+    """
+    empty if there is no manifest, snapshots can also be put together by hand
+    """
+    try:
+        with open(os.path.join(snapshot_dir(), MANIFEST_FILE), "r", encoding="utf8") as file:
+            return json.load(file)
+    except FileNotFoundError:
+        return {}
+
+
+def user_override() -> Optional[str]:
+    return os.environ.get(USER_ENV_VAR) or None
+
+
+def current_user() -> str:
+    """
+    when replaying a snapshot, act as the user who took it, unless told otherwise
+    """
+    if user_override() is not None:
+        return user_override()
+    if snapshot_dir() is not None:
+        user = snapshot_manifest().get("user")
+        if user:
+            return user
+    return os.environ["USER"]
+
+
+def my_posix_groups() -> Optional[List[str]]:
+    """
+    None if replaying a snapshot that doesn't say what groups the current user is in
+    """
+    if snapshot_dir() is not None:
+        manifest = snapshot_manifest()
+        if manifest.get("user") != current_user():
+            return None
+        return manifest.get("groups")
+    return [g.gr_name for g in grp.getgrall() if current_user() in g.gr_mem]
+
+
+def snapshot_time() -> float:
+    """
+    when the snapshot was taken according to its manifest, else when the file was written
+    """
+    manifest_time = snapshot_manifest().get("time")
+    if manifest_time:
+        return datetime.datetime.fromisoformat(manifest_time).timestamp()
+    return os.path.getmtime(snapshot_dir())
+
+
+def oldest_data_time() -> Optional[float]:
+    """
+    None if no slurm data has been collected yet
+    """
+    return min(data_times.values(), default=None)
+
+
+def snapshot_json(name: str, user=None) -> dict:
+    output = json.loads(read_snapshot_file(name))
+    data_times[name] = snapshot_time()
+    if user is not None:
+        filter_by_user(output, user)
+    return output
+
+
+def slurm_json(
+    name: str, argv: List[str], cache_file_path: Optional[str] = None, user=None, **kwargs
+) -> dict:
+    """
+    name: key in SNAPSHOT_FILES
+    argv: the command to run when not replaying a snapshot
+    cache_file_path: read this file instead of running argv if it exists. "none" disables it.
+    user: argv already filters for this user, so filter the snapshot the same way
+    kwargs: passed to subprocess.check_output
+    """
+    if snapshot_dir() is not None:
+        return snapshot_json(name, user=user)
+    if (
+        cache_file_path is not None
+        and cache_file_path.lower() != "none"
+        and os.path.isfile(cache_file_path)
+    ):
+        return _read_cache_file(name, cache_file_path)
+    output = json.loads(subp.check_output(argv, **kwargs))  # nosec
+    data_times[name] = time.time()
+    return output
+
+
+def slurm_text(name: str, argv: List[str], **kwargs) -> str:
+    """
+    the same as slurm_json, for the commands that have no JSON output
+    """
+    if snapshot_dir() is not None:
+        data_times[name] = snapshot_time()
+        return read_snapshot_file(name)
+    output = subp.check_output(argv, text=True, **kwargs)  # nosec
+    data_times[name] = time.time()
+    return output
+
+
+@functools.lru_cache(maxsize=16)
+def _parse_cache_file(cache_file_path: str, mtime: float) -> dict:  # pylint: disable=unused-argument
+    # `--watch` reads the cache file on every refresh. mtime is part of the lru_cache key,
+    # so the file is only parsed again once it has been rewritten.
+    with open(cache_file_path, "r", encoding="utf8") as file:
+        return json.load(file)
+
+
+def _read_cache_file(name: str, cache_file_path: str) -> dict:
+    mtime = os.path.getmtime(cache_file_path)
+    data_times[name] = mtime
+    # the caller may modify the output, don't let that change the cached copy
+    return copy.deepcopy(_parse_cache_file(cache_file_path, mtime))
+
+
+def filter_by_user(output: dict, user: str) -> None:
+    """
+    a snapshot has everyone's jobs and associations, `squeue --me` and
+    `sacctmgr show association user=foo` do not
+    """
+    if "jobs" in output:
+        output["jobs"] = [x for x in output["jobs"] if x.get("user_name") == user]
+    if "associations" in output:
+        output["associations"] = [x for x in output["associations"] if x.get("user") == user]
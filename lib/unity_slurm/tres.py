@@ -0,0 +1,162 @@
+"""
+TRES (trackable resources) as a map from TRES name to amount
+
+the names are the same as in a job's tres_alloc_str and in sacctmgr's limits:
+"cpu", "mem", "node", "billing", "gres/gpu", "gres/gpu:a100", "gres/shard", ...
+memory is in MB, everything else is a count.
+"""
+import re
+import sys
+from typing import Dict, Iterable, List
+
+from unity_slurm import config
+
+TresMap = Dict[str, int]
+
+MEM_SUFFIX_TO_MB = {"K": 1 / 1024, "": 1, "M": 1, "G": 1024, "T": 1024 * 1024, "P": 1024**3}
+
+# GPUs allocated without a type, in a partition with more than one type of GPU
+UNKNOWN_GPU_TYPE = "unknown"
+
+TRES_ARG = "--tres"
+TRES_HELP_LINE = (
+    f"{TRES_ARG} LIST: comma separated TRES to show, or \"all\" (default: cpu,gres/gpu,mem,billing)\n"
+    '  "all" is every TRES used by a job in the accounts, like "node" and "gres/gpu:a100"'
+)
+
+# the account usage tools show these by default
+DEFAULT_COLUMNS = ["cpu", "gres/gpu", "mem", "billing"]
+DISPLAY_NAMES = {"cpu": "CPUs", "gres/gpu": "GPUs", "mem": "memory", "node": "nodes"}
+
+
+def parse(tres_str: str) -> TresMap:
+    """
+    "cpu=4,mem=40G,node=1,billing=1,gres/gpu:a100=1" -> {"cpu": 4, "mem": 40960, "node": 1,
+    "billing": 1, "gres/gpu:a100": 1, "gres/gpu": 1}
+    a job that asked for only typed GPUs has no "gres/gpu=" entry, it is added as their sum
+    """
+    output: TresMap = {}
+    for resource in tres_str.split(","):
+        name, _, value = resource.partition("=")
+        match = re.fullmatch(r"([0-9.]+)([KMGTP]?)", value.strip())
+        if name.strip() == "" or not match:
+            continue
+        count, suffix = float(match.group(1)), match.group(2)
+        if name == "mem":
+            count *= MEM_SUFFIX_TO_MB[suffix]
+        output[name.strip()] = int(count)
+    for generic in {x.split(":")[0] for x in output if ":" in x}:
+        typed_total = sum(v for k, v in output.items() if k.startswith(generic + ":"))
+        output[generic] = max(output.get(generic, 0), typed_total)
+    return output
+
+
+def parse_mem_MB(mem: str) -> int:
+    """
+    same format as `srun --mem`: a number with an optional K/M/G/T suffix, default M
+    raises ValueError if the format is not valid
+    """
+    match = re.fullmatch(r"([0-9]+)([KMGT]?)B?", mem.strip().upper())
+    if not match:
+        raise ValueError(f'invalid memory "{mem}", expected for example "500M" or "200G"')
+    number, suffix = match.groups()
+    return int(int(number) * MEM_SUFFIX_TO_MB[suffix])
+
+
+def gpu_name_remap(gpu_type: str) -> str:
+    """
+    the name that users know a GPU type by, as shown by unity-slurm-gpu-list: "2080_ti" -> "2080ti"
+    """
+    return config.get("gpu_type_remap").get(gpu_type, gpu_type)
+
+
+def gres_gpu_type(gpu_type: str) -> str:
+    """
+    the name that slurm knows a GPU type by, the reverse of gpu_name_remap: "2080ti" -> "2080_ti"
+    """
+    unmapped = {y: x for x, y in config.get("gpu_type_remap").items()}
+    return unmapped.get(gpu_type, gpu_type)
+
+
+def guess_gpu_type(partition: str) -> str:
+    """
+    the type of the GPUs that a job in this partition was allocated without a type, named as
+    in unity-slurm-gpu-list. only known if the partition has one type of GPU, see partition2gpu
+    """
+    return config.get("partition2gpu").get(partition, UNKNOWN_GPU_TYPE)
+
+
+def gpu_type_matches(requested: str, node_gpu_type: str) -> bool:
+    """
+    requested is either name of the GPU type, node_gpu_type is the one in slurm's gres
+    """
+    return requested in [node_gpu_type, gpu_name_remap(node_gpu_type)]
+
+
+def job_tres(job: dict) -> TresMap:
+    """
+    what a job has allocated, or what it asked for if nothing is allocated yet (pending)
+    """
+    return parse(job["tres_alloc_str"] or job["tres_req_str"])
+
+
+def add(total: TresMap, other: TresMap) -> TresMap:
+    """
+    add other to total in place, and return total
+    """
+    for name, count in other.items():
+        total[name] = total.get(name, 0) + count
+    return total
+
+
+def total(tres_maps: Iterable[TresMap]) -> TresMap:
+    output: TresMap = {}
+    for tres_map in tres_maps:
+        add(output, tres_map)
+    return output
+
+
+def display_name(name: str) -> str:
+    return DISPLAY_NAMES.get(name, name)
+
+
+def format_amount(name: str, count: int) -> str:
+    if name == "mem" and count != 0:
+        if count >= 1024 * 1024:
+            return f"{count / 1024 / 1024:.3g}T"
+        return f"{count / 1024:.4g}G" if count >= 1024 else f"{count}M"
+    return str(count)
+
+
+def format_tres(name: str, count: int) -> str:
+    """
+    "mem=40G", the way that slurm shows a TRES
+    """
+    return f"{name}={format_amount(name, count)}"
+
+
+def parse_columns(columns_arg: str, seen: Iterable[str]) -> List[str]:
+    """
+    the argument of --tres, a comma separated list of TRES names, or "all" for every TRES in seen
+    """
+    if columns_arg == "all":
+        # the default columns first, then the rest in alphabetical order
+        return DEFAULT_COLUMNS + sorted(set(seen) - set(DEFAULT_COLUMNS))
+    return [x.strip() for x in columns_arg.split(",") if x.strip() != ""]
+
+
+def pop_tres_arg(argv: List[str]) -> str:
+    """
+    remove `--tres LIST` or `--tres=LIST` from argv (in place) and return LIST
+    """
+    for i, arg in enumerate(argv):
+        if arg == TRES_ARG:
+            if i + 1 >= len(argv):
+                sys.exit(f"{TRES_ARG} requires a comma separated list of TRES, or \"all\"")
+            value = argv[i + 1]
+            del argv[i : i + 2]
+            return value
+        if arg.startswith(TRES_ARG + "="):
+            del argv[i]
+            return arg.split("=", 1)[1]
+    return ",".join(DEFAULT_COLUMNS)
@@ -0,0 +1,127 @@
+"""
+`--watch SECONDS`: redraw a report in place every few seconds, like `watch`
+
+cells that changed since the last refresh are highlighted. a cell is a run of non whitespace,
+and each line is compared with a line from the last refresh that starts with the same cell,
+so that a new row doesn't highlight every row below it.
+"""
+import re
+import sys
+import time
+import datetime
+from typing import Callable, List, Optional
+
+from unity_slurm import config, slurm
+
+WATCH_ARG = "--watch"
+
+HIGHLIGHT = "\033[7m"  # reverse video
+HIGHLIGHT_END = "\033[27m"
+ANSI_ESCAPE = re.compile(r"\x1b\[[0-9;]*m")
+
+
+def pop_watch_arg(argv: List[str]) -> Optional[float]:
+    """
+    remove `--watch SECONDS` or `--watch=SECONDS` from argv (in place) and return SECONDS
+    """
+    for i, arg in enumerate(argv):
+        if arg == WATCH_ARG:
+            if i + 1 >= len(argv):
+                sys.exit(f"{WATCH_ARG} requires a number of seconds")
+            value = argv[i + 1]
+            del argv[i : i + 2]
+            return _parse_interval(value)
+        if arg.startswith(WATCH_ARG + "="):
+            del argv[i]
+            return _parse_interval(arg.split("=", 1)[1])
+    return None
+
+
+def _parse_interval(value: str) -> float:
+    try:
+        interval_s = float(value)
+    except ValueError:
+        sys.exit(f'{WATCH_ARG} expects a number of seconds, not "{value}"')
+    if interval_s <= 0:
+        sys.exit(f"{WATCH_ARG} expects a positive number of seconds")
+    return interval_s
+
+
+def check_format(fmt: str) -> None:
+    if fmt != "table":
+        sys.exit(f'{WATCH_ARG} only works with "--format table"')
+
+
+def clear_terminal_scrollback():
+    sys.stdout.write("\033c\033[3J")
+    sys.stdout.flush()
+
+
+def _row_key(line: str) -> str:
+    cells = ANSI_ESCAPE.sub("", line).split()
+    return cells[0] if cells else ""
+
+
+def highlight_changes(old_lines: List[str], new_lines: List[str]) -> List[str]:
+    old_lines_by_key = {}
+    for line in old_lines:
+        old_lines_by_key.setdefault(_row_key(line), line)
+    output = []
+    for i, line in enumerate(new_lines):
+        key = _row_key(line)
+        if i < len(old_lines) and _row_key(old_lines[i]) == key:
+            old_line = old_lines[i]
+        elif key in old_lines_by_key:
+            old_line = old_lines_by_key[key]
+        elif i < len(old_lines):
+            old_line = old_lines[i]
+        else:
+            old_line = ""
+        # alternate_brightness tables put codes around every other row, so a row that moves
+        # up or down changes its codes but not its cells
+        old_cells = re.split(r"(\s+)", ANSI_ESCAPE.sub("", old_line))
+        new_cells = re.split(r"(\s+)", ANSI_ESCAPE.sub("", line))
+        changed = False
+        for j, cell in enumerate(new_cells):
+            if cell.strip() and (j >= len(old_cells) or old_cells[j] != cell):
+                new_cells[j] = HIGHLIGHT + cell + HIGHLIGHT_END
+                changed = True
+        # the highlight goes on the text without its codes, which could otherwise cancel it
+        output.append("".join(new_cells) if changed else line)
+    return output
+
+
+def header(title: str, interval_s: float) -> str:
+    data_time = slurm.oldest_data_time()
+    if data_time is None:
+        data_time_str = "unknown"
+    else:
+        data_time_str = datetime.datetime.fromtimestamp(data_time).strftime("%Y-%m-%d %H:%M:%S")
+    return f"every {interval_s:g}s: {title}    data from {data_time_str}    press Ctrl+C to exit"
+
+
+def watch(render: Callable[[], List[str]], interval_s: float, title: str) -> None:
+    """
+    render: collects fresh data from slurm and returns the lines to print
+    the interval is at least the "watch_min_interval_s" config value, to go easy on slurmctld
+    """
+    min_interval_s = config.get("watch_min_interval_s")
+    if interval_s < min_interval_s:
+        print(
+            f"{WATCH_ARG} {interval_s:g} is too often, using {min_interval_s:g} seconds instead",
+            file=sys.stderr,
+        )
+        interval_s = min_interval_s
+    old_lines = None
+    try:
+        while True:
+            slurm.data_times.clear()
+            new_lines = render()
+            shown_lines = new_lines if old_lines is None else highlight_changes(old_lines, new_lines)
+            clear_terminal_scrollback()
+            print(header(title, interval_s))
+            print("\n".join(shown_lines), flush=True)
+            old_lines = new_lines
+            time.sleep(interval_s)
+    except KeyboardInterrupt:
+        print()
@@ -0,0 +1,655 @@
+"""
+runs each command in ../bin against the recorded slurm output in fixtures/, using the
+stub slurm commands in stubs/, and compares what it prints with the files in golden/
+
+    python3 -m unittest discover tests
+
+after an intentional change to the output, regenerate the golden files and review the diff:
+
+    UPDATE_GOLDEN=1 python3 -m unittest discover tests
+
+fixtures/basic is data_parser v0.0.39, fixtures/v0.0.40 and fixtures/v0.0.41 have the same
+cluster state in the newer shapes. They all include a multi-node GPU job, a down node, pending
+jobs with an empty tres_alloc_str, and node gres with "(S:0-1)" suffixes.
+"""
+import os
+import json
+import shutil
+import tempfile
+import unittest
+import subprocess
+from typing import List, Optional
+
+TESTS_DIR = os.path.dirname(os.path.abspath(__file__))
+REPO_DIR = os.path.dirname(TESTS_DIR)
+BIN_DIR = os.path.join(REPO_DIR, "bin")
+STUBS_DIR = os.path.join(TESTS_DIR, "stubs")
+FIXTURES_DIR = os.path.join(TESTS_DIR, "fixtures")
+GOLDEN_DIR = os.path.join(TESTS_DIR, "golden")
+UPDATE_GOLDEN = os.environ.get("UPDATE_GOLDEN", "") not in ["", "0"]
+
+TEST_USER = "alice"
+
+
+def run_tool(
+    argv: List[str], fixture="basic", stdin="", env: Optional[dict] = None, cwd=None
+) -> str:
+    """
+    run a command from bin/ with the slurm stubs, return a transcript of everything it did
+    """
+    with tempfile.TemporaryDirectory() as tmp_dir:
+        slurm_log = os.path.join(tmp_dir, "slurm.log")
+        full_env = {
+            "PATH": STUBS_DIR + os.pathsep + os.environ["PATH"],
+            "UNITY_SLURM_BIN_DIR": STUBS_DIR,
+            "UNITY_SLURM_TEST_FIXTURE": os.path.join(FIXTURES_DIR, fixture),
+            "UNITY_SLURM_TEST_LOG": slurm_log,
+            "USER": TEST_USER,
+            "HOME": tmp_dir,
+            "PAGER": "none",
+            "SINFO_CACHE_FILE": "none",
+            "SINFO_CACHE_FILE_PATH": "none",
+            "SINFO_N_CACHE_FILE_PATH": "none",
+        }
+        if env is not None:
+            full_env.update(env)
+        proc = subprocess.run(
+            [os.path.join(BIN_DIR, argv[0])] + argv[1:],
+            input=stdin,
+            capture_output=True,
+            text=True,
+            env=full_env,
+            cwd=cwd or tmp_dir,
+            timeout=60,
+            check=False,
+        )
+        transcript = (
+            f"--- exit code: {proc.returncode}\n"
+            f"--- stdout\n{proc.stdout}"
+            f"--- stderr\n{proc.stderr}"
+        )
+        if os.path.isfile(slurm_log):
+            with open(slurm_log, "r", encoding="utf8") as file:
+                transcript += f"--- slurm commands\n{file.read()}"
+        return transcript.replace(REPO_DIR, "$REPO")
+
+
+class GoldenTestCase(unittest.TestCase):
+    def assert_golden(self, name: str, transcript: str):
+        golden_path = os.path.join(GOLDEN_DIR, f"{name}.txt")
+        if UPDATE_GOLDEN:
+            with open(golden_path, "w", encoding="utf8") as file:
+                file.write(transcript)
+            return
+        if not os.path.isfile(golden_path):
+            self.fail(f"{golden_path} does not exist. Run with UPDATE_GOLDEN=1 to create it.")
+        with open(golden_path, "r", encoding="utf8") as file:
+            self.assertEqual(file.read(), transcript)
+
+    def assert_tool_golden(self, name: str, argv: List[str], **kwargs):
+        self.assert_golden(name, run_tool(argv, **kwargs))
+
+
+def snapshot_arg(fixture="basic") -> List[str]:
+    return ["--from-snapshot", os.path.join(FIXTURES_DIR, fixture)]
+
+
+def config_fixture(name: str) -> str:
+    return os.path.join(FIXTURES_DIR, "config", name)
+
+
+class TestNodeUsage(GoldenTestCase):
+    def test_all_nodes(self):
+        self.assert_tool_golden("node-usage", ["unity-slurm-node-usage"])
+
+    def test_v0_0_40(self):
+        self.assert_tool_golden("node-usage", ["unity-slurm-node-usage"], fixture="v0.0.40")
+
+    def test_v0_0_41(self):
+        self.assert_tool_golden("node-usage", ["unity-slurm-node-usage"], fixture="v0.0.41")
+
+    def test_stdin_filter(self):
+        self.assert_tool_golden(
+            "node-usage-stdin", ["unity-slurm-node-usage"], stdin="gpu001\ngpu002\n"
+        )
+
+    def test_unknown_hostname(self):
+        self.assert_tool_golden(
+            "node-usage-unknown-host", ["unity-slurm-node-usage"], stdin="nope001\n"
+        )
+
+    def test_hostlist_argument(self):
+        self.assert_tool_golden(
+            "node-usage-hostlist", ["unity-slurm-node-usage", "cpu[001,003]", "gpu00[1-2]"]
+        )
+
+    def test_invalid_hostlist(self):
+        self.assert_tool_golden(
+            "node-usage-invalid-hostlist", ["unity-slurm-node-usage"], stdin="gpu[001-\n"
+        )
+
+    def test_down_node_requested(self):
+        # down nodes are not an error, they are just not shown
+        self.assert_tool_golden(
+            "node-usage-down-node", ["unity-slurm-node-usage"], stdin="cpu002\ncpu001\n"
+        )
+
+    def test_json(self):
+        self.assert_tool_golden("node-usage-json", ["unity-slurm-node-usage", "--format", "json"])
+
+    def test_help(self):
+        self.assert_tool_golden("node-usage-help", ["unity-slurm-node-usage", "--help"])
+
+    def test_snapshot_matches_live(self):
+        live = run_tool(["unity-slurm-node-usage"])
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            # without a manifest, the snapshot is replayed as $USER with no POSIX groups
+            snapshot_dir = os.path.join(tmp_dir, "snapshot")
+            shutil.copytree(os.path.join(FIXTURES_DIR, "basic"), snapshot_dir)
+            os.remove(os.path.join(snapshot_dir, "manifest.json"))
+            replay = run_tool(
+                ["unity-slurm-node-usage", "--from-snapshot", snapshot_dir],
+                env={"UNITY_SLURM_BIN_DIR": "/nonexistent"},
+            )
+        self.assertEqual(live, replay)
+
+
+class TestPartitionUsage(GoldenTestCase):
+    def test_table(self):
+        self.assert_tool_golden("partition-usage", ["unity-slurm-partition-usage"])
+
+    def test_v0_0_40(self):
+        self.assert_tool_golden(
+            "partition-usage", ["unity-slurm-partition-usage"], fixture="v0.0.40"
+        )
+
+    def test_v0_0_41(self):
+        self.assert_tool_golden(
+            "partition-usage", ["unity-slurm-partition-usage"], fixture="v0.0.41"
+        )
+
+    def test_csv(self):
+        self.assert_tool_golden(
+            "partition-usage-csv", ["unity-slurm-partition-usage", "--format", "csv"]
+        )
+
+    def test_config_override(self):
+        self.assert_tool_golden(
+            "partition-usage-config",
+            ["unity-slurm-partition-usage"],
+            env={"UNITY_SLURM_CONFIG": config_fixture("override.json")},
+        )
+
+
+class TestGpuList(GoldenTestCase):
+    def test_table(self):
+        self.assert_tool_golden("gpu-list", ["unity-slurm-gpu-list"])
+
+    def test_v0_0_40(self):
+        self.assert_tool_golden("gpu-list", ["unity-slurm-gpu-list"], fixture="v0.0.40")
+
+    def test_v0_0_41(self):
+        self.assert_tool_golden("gpu-list", ["unity-slurm-gpu-list"], fixture="v0.0.41")
+
+    def test_gpu_usage_alias(self):
+        alias = run_tool(["unity-slurm-gpu-usage"])
+        original = run_tool(["unity-slurm-gpu-list"])
+        self.assertEqual(alias, original)
+
+    def test_sort_free(self):
+        self.assert_tool_golden("gpu-list-sort-free", ["unity-slurm-gpu-list", "--sort", "free"])
+
+    def test_tsv(self):
+        self.assert_tool_golden("gpu-list-tsv", ["unity-slurm-gpu-list", "--format", "tsv"])
+
+    def test_mine(self):
+        self.assert_tool_golden(
+            "gpu-list-mine",
+            ["unity-slurm-gpu-list", "--mine"],
+            env={"UNITY_SLURM_USER": "bob"},
+        )
+
+    def test_sacctmgr_hangs(self):
+        # "Can Submit" is unknown after 10s, rather than waiting on slurmdbd
+        self.assert_tool_golden(
+            "gpu-list-sacctmgr-hangs",
+            ["unity-slurm-gpu-list"],
+            env={"UNITY_SLURM_TEST_HANG": "sacctmgr"},
+        )
+
+    def test_partitions_json(self):
+        self.assert_tool_golden(
+            "gpu-list-json", ["unity-slurm-gpu-list", "--mine", "--format", "json"]
+        )
+
+    def test_config_override(self):
+        self.assert_tool_golden(
+            "gpu-list-config",
+            ["unity-slurm-gpu-list"],
+            env={"UNITY_SLURM_CONFIG": config_fixture("override.json")},
+        )
+
+    def test_missing_config(self):
+        self.assert_tool_golden(
+            "gpu-list-missing-config",
+            ["unity-slurm-gpu-list"],
+            env={"UNITY_SLURM_CONFIG": "/nonexistent/config.json"},
+        )
+
+    def test_user_config(self):
+        with tempfile.TemporaryDirectory() as home:
+            os.makedirs(os.path.join(home, ".config", "unity-slurm"))
+            shutil.copy(
+                config_fixture("override.json"),
+                os.path.join(home, ".config", "unity-slurm", "config.json"),
+            )
+            user_config = run_tool(["unity-slurm-gpu-list"], env={"HOME": home})
+        self.assertEqual(
+            user_config,
+            run_tool(
+                ["unity-slurm-gpu-list"],
+                env={"UNITY_SLURM_CONFIG": config_fixture("override.json")},
+            ),
+        )
+
+
+class TestAccountUsage(GoldenTestCase):
+    # the live commands read the POSIX groups of whoever runs the tests, so replay a snapshot
+    def test_table(self):
+        self.assert_tool_golden("account-usage", ["unity-slurm-account-usage"] + snapshot_arg())
+
+    def test_v0_0_40(self):
+        self.assert_tool_golden(
+            "account-usage",
+            ["unity-slurm-account-usage"] + snapshot_arg("v0.0.40"),
+            fixture="v0.0.40",
+        )
+
+    def test_v0_0_41(self):
+        self.assert_tool_golden(
+            "account-usage",
+            ["unity-slurm-account-usage"] + snapshot_arg("v0.0.41"),
+            fixture="v0.0.41",
+        )
+
+    def test_json(self):
+        self.assert_tool_golden(
+            "account-usage-json",
+            ["unity-slurm-account-usage", "--format", "json"] + snapshot_arg(),
+        )
+
+    def test_all_tres_csv(self):
+        self.assert_tool_golden(
+            "account-usage-all-tres",
+            ["unity-slurm-account-usage", "--tres", "all", "--format", "csv"] + snapshot_arg(),
+        )
+
+    def test_tres_columns(self):
+        self.assert_tool_golden(
+            "account-usage-tres",
+            ["unity-slurm-account-usage", "--account", "pi_uri", "--tres", "cpu,mem,node"],
+        )
+
+    def test_total_usage(self):
+        self.assert_tool_golden(
+            "account-total-usage", ["unity-slurm-account-total-usage"] + snapshot_arg()
+        )
+
+    def test_all_my_accounts(self):
+        # alice is in pi_uri through a slurm association but not a POSIX group
+        self.assert_tool_golden(
+            "account-total-usage-all-my-accounts",
+            ["unity-slurm-account-total-usage", "--all-my-accounts"] + snapshot_arg(),
+        )
+
+    def test_total_usage_slurm_down(self):
+        # it runs at login, so it exits 0 with one line rather than a traceback
+        self.assert_tool_golden(
+            "account-total-usage-slurm-down",
+            ["unity-slurm-account-total-usage", "--account", "pi_alice"],
+            env={"UNITY_SLURM_TEST_FAIL": "squeue"},
+        )
+
+    def test_total_usage_sacctmgr_hangs(self):
+        # --account checks that the account exists with sacctmgr, which gives up after 10s
+        self.assert_tool_golden(
+            "account-total-usage-slurm-down",
+            ["unity-slurm-account-total-usage", "--account", "pi_alice"],
+            env={"UNITY_SLURM_TEST_HANG": "sacctmgr"},
+        )
+
+    def test_other_account(self):
+        self.assert_tool_golden(
+            "account-usage-pi_uri", ["unity-slurm-account-usage", "--account", "pi_uri"]
+        )
+
+    def test_unknown_account(self):
+        self.assert_tool_golden(
+            "account-usage-unknown", ["unity-slurm-account-usage", "--account", "pi_nobody"]
+        )
+
+
+class TestAccountHistory(GoldenTestCase):
+    # the days start at local midnight
+    ENV = {"TZ": "UTC"}
+    RANGE = ["--account", "pi_alice", "--since", "2025-10-01", "--until", "2025-10-08"]
+
+    def test_by_day(self):
+        # job 93 runs from 2025-10-04 to 2025-10-06 and is split between the days
+        self.assert_tool_golden(
+            "account-history-by-day",
+            ["unity-slurm-account-history", "--by", "day"] + self.RANGE,
+            env=self.ENV,
+        )
+
+    def test_csv(self):
+        # job 94 was allocated a GPU without a type, in a partition that only has 2080ti
+        self.assert_tool_golden(
+            "account-history-csv",
+            ["unity-slurm-account-history", "--format", "csv"] + self.RANGE,
+            env=self.ENV,
+        )
+
+    def test_since_after_until(self):
+        self.assert_tool_golden(
+            "account-history-bad-range",
+            ["unity-slurm-account-history", "--account", "pi_alice"]
+            + ["--since", "2025-10-08", "--until", "2025-10-01"],
+            env=self.ENV,
+        )
+
+
+class TestFairshare(GoldenTestCase):
+    def test_all_my_accounts(self):
+        self.assert_tool_golden(
+            "fairshare",
+            ["unity-slurm-fairshare", "--all-my-accounts"] + snapshot_arg(),
+        )
+
+    def test_v0_0_40(self):
+        self.assert_tool_golden(
+            "fairshare",
+            ["unity-slurm-fairshare", "--all-my-accounts"] + snapshot_arg("v0.0.40"),
+            fixture="v0.0.40",
+        )
+
+
+class TestAccountList(GoldenTestCase):
+    def test_other_user(self):
+        self.assert_tool_golden("account-list-carol", ["unity-slurm-account-list", "carol"])
+
+    def test_snapshot_user(self):
+        self.assert_tool_golden("account-list", ["unity-slurm-account-list"] + snapshot_arg())
+
+    def test_tree(self):
+        self.assert_tool_golden("account-list-tree", ["unity-slurm-account-list", "--tree"])
+
+    def test_csv(self):
+        self.assert_tool_golden(
+            "account-list-csv", ["unity-slurm-account-list", "--format", "csv"]
+        )
+
+
+class TestFindNodes(GoldenTestCase):
+    def test_feature(self):
+        self.assert_tool_golden("find-nodes-intel", ["unity-slurm-find-nodes", "intel"])
+
+    def test_and_or(self):
+        self.assert_tool_golden(
+            "find-nodes-and-or", ["unity-slurm-find-nodes", "(a100|2080ti)&x86_64"]
+        )
+
+    def test_count(self):
+        self.assert_tool_golden("find-nodes-count", ["unity-slurm-find-nodes", "intel&ib*3"])
+
+    def test_matching_or(self):
+        self.assert_tool_golden("find-nodes-brackets", ["unity-slurm-find-nodes", "[amd|intel]"])
+
+    def test_unknown_feature(self):
+        self.assert_tool_golden("find-nodes-unknown", ["unity-slurm-find-nodes", "intel&avx512"])
+
+    def test_invalid(self):
+        self.assert_tool_golden("find-nodes-invalid", ["unity-slurm-find-nodes", "intel&(ib"])
+
+    def test_idle_resources(self):
+        self.assert_tool_golden(
+            "find-nodes-idle",
+            ["unity-slurm-find-nodes", "x86_64", "--min-idle-cpus", "32"]
+            + ["--min-free-mem", "200G"],
+        )
+
+    def test_gpus_accessible(self):
+        self.assert_tool_golden(
+            "find-nodes-gpus-accessible",
+            ["unity-slurm-find-nodes", "--gpu", "a100", "--min-idle-gpus", "2"]
+            + ["--accessible-only"],
+        )
+
+    def test_pipe_to_node_usage(self):
+        transcript = run_tool(["unity-slurm-find-nodes", "intel", "--min-idle-cpus", "1"])
+        hostnames = transcript.split("--- stdout\n")[1].split("--- stderr")[0]
+        self.assert_tool_golden(
+            "find-nodes-node-usage", ["unity-slurm-node-usage"], stdin=hostnames
+        )
+
+    def test_hostlist(self):
+        self.assert_tool_golden(
+            "find-nodes-hostlist", ["unity-slurm-find-nodes", "x86_64", "--hostlist"]
+        )
+
+    def test_snapshot(self):
+        self.assert_tool_golden(
+            "find-nodes-v0.0.40",
+            ["unity-slurm-find-nodes", "amd"] + snapshot_arg("v0.0.40"),
+        )
+
+    def test_no_arguments(self):
+        self.assert_tool_golden("find-nodes-no-args", ["unity-slurm-find-nodes"])
+
+
+class TestListConstraints(GoldenTestCase):
+    # stdout is a pipe in the tests, so the tables need "--format table"
+    def test_live(self):
+        self.assert_tool_golden(
+            "list-constraints", ["unity-slurm-list-constraints", "--format", "table"]
+        )
+
+    def test_snapshot(self):
+        self.assert_tool_golden(
+            "list-constraints",
+            ["unity-slurm-list-constraints", "--format", "table"] + snapshot_arg(),
+        )
+
+    def test_piped(self):
+        self.assert_tool_golden("list-constraints-piped", ["unity-slurm-list-constraints"])
+
+    def test_csv(self):
+        self.assert_tool_golden(
+            "list-constraints-csv",
+            ["unity-slurm-list-constraints", "--format", "csv"] + snapshot_arg("v0.0.40"),
+        )
+
+    def test_site_descriptions(self):
+        self.assert_tool_golden(
+            "list-constraints-config",
+            ["unity-slurm-list-constraints", "--format", "table"],
+            env={"UNITY_SLURM_CONFIG": config_fixture("features.json")},
+        )
+
+
+class TestJobTimeUsage(GoldenTestCase):
+    def test_default(self):
+        self.assert_tool_golden("job-time-usage", ["unity-slurm-job-time-usage"])
+
+    def test_one_job(self):
+        self.assert_tool_golden("job-time-usage-1", ["unity-slurm-job-time-usage", "1"])
+
+    def test_snapshot_matches_live(self):
+        live = run_tool(["unity-slurm-job-time-usage"])
+        replay = run_tool(["unity-slurm-job-time-usage"] + snapshot_arg())
+        self.assertEqual(live, replay)
+
+    def test_invalid_argument(self):
+        self.assert_tool_golden(
+            "job-time-usage-invalid", ["unity-slurm-job-time-usage", "many"]
+        )
+
+
+class TestJobTop(GoldenTestCase):
+    def test_snapshot(self):
+        self.assert_tool_golden("job-top", ["unity-slurm-job-top"] + snapshot_arg())
+
+
+class TestCompute(GoldenTestCase):
+    def test_cores(self):
+        self.assert_tool_golden("compute-4", ["unity-compute", "4"])
+
+    def test_invalid_argument(self):
+        self.assert_tool_golden("compute-invalid", ["unity-compute", "four"])
+
+    def test_zero_cores(self):
+        for argv in [["-c", "0"], ["0"]]:
+            with self.subTest(argv=argv):
+                self.assert_tool_golden("compute-zero-cores", ["unity-compute"] + argv)
+
+    def test_resources(self):
+        self.assert_tool_golden(
+            "compute-gpu",
+            ["unity-compute", "-c", "8", "--mem", "16G", "-G", "2080ti:2", "-t", "2:00:00"]
+            + ["-p", "gpu", "-A", "pi_alice", "-C", "intel"],
+        )
+
+    def test_inaccessible_partition(self):
+        self.assert_tool_golden(
+            "compute-inaccessible", ["unity-compute", "-p", "uri-gpu", "-A", "pi_alice"]
+        )
+
+    def test_auto_partition_gpu(self):
+        # an owned partition is preferred to a general one
+        self.assert_tool_golden("compute-auto-gpu", ["unity-compute", "-G", "1"])
+
+    def test_auto_partition_shortest_wait(self):
+        # pi_alice's GrpTRES allows only 4 GPUs
+        self.assert_tool_golden(
+            "compute-auto-wait", ["unity-compute", "-G", "2080ti:8", "-A", "pi_uri"]
+        )
+
+    def test_auto_partition_impossible(self):
+        self.assert_tool_golden("compute-auto-impossible", ["unity-compute", "-c", "1000"])
+
+    def test_dry_run(self):
+        self.assert_tool_golden(
+            "compute-dry-run",
+            ["unity-compute", "-c", "4", "-t", "1:00:00", "-C", "intel", "-p", "cpu", "--dry-run"],
+        )
+
+    def test_dry_run_remapped_gpu_type(self):
+        # gpu-list shows 2080_ti as 2080ti, srun has to be given 2080_ti
+        self.assert_tool_golden(
+            "compute-dry-run-gpu-type",
+            ["unity-compute", "-G", "2080ti:1", "-p", "gpu", "--dry-run"],
+        )
+
+    def test_waits_for_limit(self):
+        # pi_alice has GrpTRES cpu=32 and its running jobs outside of preempt partitions use 20
+        self.assert_tool_golden("compute-limit-wait", ["unity-compute", "-c", "16", "-p", "cpu"])
+
+    def test_exceeds_limit(self):
+        self.assert_tool_golden(
+            "compute-limit-exceeded", ["unity-compute", "-G", "2080ti:8", "-A", "pi_alice"]
+        )
+
+    def test_attach(self):
+        # 103 is alice's only running interactive job
+        self.assert_tool_golden("compute-attach", ["unity-compute", "--attach"])
+
+    def test_attach_not_my_job(self):
+        self.assert_tool_golden("compute-attach-not-mine", ["unity-compute", "--attach", "104"])
+
+    def test_persistent_dry_run(self):
+        self.assert_tool_golden(
+            "compute-persistent-dry-run",
+            ["unity-compute", "--persistent", "-p", "cpu", "--dry-run"],
+        )
+
+    def test_request_does_not_fit(self):
+        self.assert_tool_golden(
+            "compute-too-big",
+            ["unity-compute", "-c", "500", "-G", "a100:1", "-t", "3-0", "-p", "gpu"],
+        )
+
+    def test_constraint_expression(self):
+        self.assert_tool_golden(
+            "compute-constraint-or", ["unity-compute", "-p", "gpu", "-C", "a100|v100"]
+        )
+
+    def test_invalid_constraint(self):
+        self.assert_tool_golden(
+            "compute-invalid-constraint", ["unity-compute", "-p", "cpu", "-C", "intel|"]
+        )
+
+
+class TestDispatcher(GoldenTestCase):
+    def test_help(self):
+        self.assert_tool_golden("unity-slurm-help", ["unity-slurm", "--help"])
+
+    def test_subcommand_matches_command(self):
+        for subcommand, command in [
+            (["node-usage"], ["unity-slurm-node-usage"]),
+            (["gpu-usage", "--sort", "free"], ["unity-slurm-gpu-list", "--sort", "free"]),
+            (["job-time-usage", "2"], ["unity-slurm-job-time-usage", "2"]),
+            (["help", "partition-usage"], ["unity-slurm-partition-usage", "--help"]),
+        ]:
+            with self.subTest(subcommand=subcommand):
+                self.assertEqual(run_tool(["unity-slurm"] + subcommand), run_tool(command))
+
+    def test_global_flags_anywhere(self):
+        before = run_tool(["unity-slurm", "--format", "csv", "--no-pager", "partition-usage"])
+        after = run_tool(["unity-slurm", "partition-usage", "--no-pager", "--format=csv"])
+        self.assertEqual(before, after)
+        self.assertEqual(before, run_tool(["unity-slurm-partition-usage", "--format", "csv"]))
+
+    def test_snapshot_and_user(self):
+        self.assert_tool_golden(
+            "unity-slurm-account-list-bob",
+            ["unity-slurm", "--user", "bob", "--snapshot", os.path.join(FIXTURES_DIR, "basic")]
+            + ["account-list"],
+        )
+
+    def test_unsupported_flag(self):
+        self.assert_tool_golden(
+            "unity-slurm-unsupported-flag", ["unity-slurm", "job-top", "--format", "json"]
+        )
+
+    def test_unknown_subcommand(self):
+        self.assert_tool_golden("unity-slurm-unknown", ["unity-slurm", "gpu-lst"])
+
+
+class TestSnapshot(unittest.TestCase):
+    def test_record_and_replay(self):
+        with tempfile.TemporaryDirectory() as output_dir:
+            run_tool(["unity-slurm-snapshot", "-o", output_dir, "--no-archive"])
+            [snapshot_name] = os.listdir(output_dir)
+            snapshot_dir = os.path.join(output_dir, snapshot_name)
+            with open(os.path.join(snapshot_dir, "manifest.json"), "r", encoding="utf8") as file:
+                manifest = json.load(file)
+            self.assertEqual(manifest["user"], TEST_USER)
+            self.assertEqual(manifest["slurm_version"], "slurm 23.11.4")
+            self.assertEqual(
+                [name for name, info in manifest["files"].items() if "error" in info], []
+            )
+            live = run_tool(["unity-slurm-partition-usage"])
+            replay = run_tool(
+                ["unity-slurm-partition-usage", "--from-snapshot", snapshot_dir],
+                env={"UNITY_SLURM_BIN_DIR": "/nonexistent"},
+            )
+            self.assertEqual(live, replay)
+
+    def test_archive(self):
+        with tempfile.TemporaryDirectory() as output_dir:
+            run_tool(["unity-slurm-snapshot", "-o", output_dir])
+            self.assertEqual(len([x for x in os.listdir(output_dir) if x.endswith(".tar.gz")]), 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,123 @@
+"""
+`--watch` redraws forever, so these run it for a moment and then interrupt it like Ctrl+C
+"""
+import os
+import sys
+import json
+import signal
+import tempfile
+import unittest
+import subprocess
+
+from test_tools import BIN_DIR, FIXTURES_DIR, REPO_DIR, STUBS_DIR, TEST_USER
+
+sys.path.insert(0, os.path.join(REPO_DIR, "lib"))
+from unity_slurm import watch  # pylint: disable=wrong-import-position
+
+HEADER_PREFIX = "every 0.2s: "
+
+
+def run_watch(argv, duration_s=1.5) -> subprocess.CompletedProcess:
+    with tempfile.TemporaryDirectory() as tmp_dir:
+        config_path = os.path.join(tmp_dir, "config.json")
+        with open(config_path, "w", encoding="utf8") as file:
+            json.dump({"watch_min_interval_s": 0}, file)
+        env = {
+            "PATH": os.environ["PATH"],
+            "UNITY_SLURM_BIN_DIR": STUBS_DIR,
+            "UNITY_SLURM_TEST_FIXTURE": os.path.join(FIXTURES_DIR, "basic"),
+            "UNITY_SLURM_CONFIG": config_path,
+            "USER": TEST_USER,
+            "HOME": tmp_dir,
+            "TZ": "UTC",
+            "SINFO_CACHE_FILE": "none",
+            "SINFO_CACHE_FILE_PATH": "none",
+            "SINFO_N_CACHE_FILE_PATH": "none",
+        }
+        with subprocess.Popen(
+            [os.path.join(BIN_DIR, argv[0])] + argv[1:],
+            stdin=subprocess.DEVNULL,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.PIPE,
+            text=True,
+            env=env,
+        ) as proc:
+            try:
+                proc.wait(timeout=duration_s)
+            except subprocess.TimeoutExpired:
+                proc.send_signal(signal.SIGINT)
+            stdout, stderr = proc.communicate(timeout=10)
+            return subprocess.CompletedProcess(proc.args, proc.returncode, stdout, stderr)
+
+
+class TestWatch(unittest.TestCase):
+    def test_redraws(self):
+        for argv in [
+            ["unity-slurm-gpu-list"],
+            ["unity-slurm-partition-usage"],
+            ["unity-slurm-node-usage"],
+            ["unity-slurm-account-usage", "--from-snapshot", os.path.join(FIXTURES_DIR, "basic")],
+        ]:
+            with self.subTest(argv=argv):
+                proc = run_watch(argv + ["--watch", "0.2"])
+                self.assertEqual(proc.returncode, 0, proc.stderr)
+                self.assertGreaterEqual(proc.stdout.count(HEADER_PREFIX + argv[0]), 2)
+                # nothing changes in between, so nothing is highlighted
+                self.assertNotIn(watch.HIGHLIGHT, proc.stdout)
+                # the pager footer has no place in a redrawn table
+                self.assertNotIn("press Q to exit", proc.stdout)
+
+    def test_snapshot_data_time(self):
+        proc = run_watch(
+            ["unity-slurm-gpu-list", "--watch", "0.2"]
+            + ["--from-snapshot", os.path.join(FIXTURES_DIR, "basic")]
+        )
+        self.assertIn("data from 2025-10-18 16:00:00", proc.stdout)
+
+    def test_machine_readable_format(self):
+        for argv in [
+            ["unity-slurm-gpu-list"],
+            ["unity-slurm-partition-usage"],
+            ["unity-slurm-node-usage"],
+            ["unity-slurm-account-usage", "--from-snapshot", os.path.join(FIXTURES_DIR, "basic")],
+        ]:
+            with self.subTest(argv=argv):
+                proc = run_watch(argv + ["--watch", "0.2", "--format", "json"])
+                self.assertEqual(proc.returncode, 1)
+                self.assertIn('--watch only works with "--format table"', proc.stderr)
+
+
+class TestHighlightChanges(unittest.TestCase):
+    def test_changed_cell(self):
+        self.assertEqual(
+            watch.highlight_changes(["gpu   1/8   0"], ["gpu   2/8   0"]),
+            [f"gpu   {watch.HIGHLIGHT}2/8{watch.HIGHLIGHT_END}   0"],
+        )
+
+    def test_rows_matched_by_first_cell(self):
+        old = ["a100   1", "v100   2"]
+        new = ["a40    5", "a100   1", "v100   2"]
+        self.assertEqual(
+            watch.highlight_changes(old, new),
+            [f"{watch.HIGHLIGHT}a40{watch.HIGHLIGHT_END}    {watch.HIGHLIGHT}5{watch.HIGHLIGHT_END}"]
+            + new[1:],
+        )
+
+    def test_ansi_codes_are_not_cells(self):
+        bright_row = "\033[0;1mcpu   4/8\033[0m"
+        self.assertEqual(watch.highlight_changes([bright_row], [bright_row]), [bright_row])
+
+    def test_row_inserted_above_bright_rows(self):
+        # like fmt_table(alternate_brightness=True), every other row below the header is bright
+        def alternate(rows):
+            return [f"\033[0;1m{x}\033[0m" if i % 2 == 1 else x for i, x in enumerate(rows)]
+
+        old = alternate(["cpu001   4/8", "cpu002   0/8", "cpu003   8/8"])
+        new = alternate(["cpu000   1/8", "cpu001   4/8", "cpu002   0/8", "cpu003   8/8"])
+        output = watch.highlight_changes(old, new)
+        self.assertIn(watch.HIGHLIGHT, output[0])
+        self.assertEqual(output[1:], new[1:])
+
+
+if __name__ == "__main__":
+    unittest.main()